@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// The parser has no dependencies on the rest of Castiron, so it is built here on its own.
+#[path = "../../src/parsing/mod.rs"]
+#[allow(dead_code)]
+mod parsing;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(content) = std::str::from_utf8(data) {
+        let _ = parsing::parse_feed(content);
+    }
+});
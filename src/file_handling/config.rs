@@ -1,36 +1,55 @@
 use std::{
     fs::File,
     io::{BufReader, BufWriter, Write},
-    path::Path,
 };
 
-use crate::types::config::CastironConfig;
+use crate::file_handling::paths::paths;
+use crate::types::config::{
+    default_connect_timeout_seconds, default_download_concurrency,
+    default_feed_refresh_concurrency, default_feed_request_timeout_seconds,
+    default_mark_played_threshold_seconds, default_max_redirects, default_read_timeout_seconds,
+    default_skip_back_seconds, default_skip_forward_seconds, default_user_agent, CastironConfig,
+};
 use crate::types::errors::CustomError;
 use iced::Theme;
 use serde_json::{from_reader, to_writer};
 
 pub fn read_config() -> Result<CastironConfig, CustomError> {
-    let config_file = File::open(Path::new("./castiron_config.json"))?;
+    let config_file = File::open(paths().config_file())?;
     let config: CastironConfig = from_reader(BufReader::new(config_file))?;
     Ok(config)
 }
 
+/// The settings a new config file starts with.
+pub fn default_config() -> CastironConfig {
+    CastironConfig {
+        theme: Theme::default().to_string(),
+        mark_played_threshold_seconds: default_mark_played_threshold_seconds(),
+        skip_back_seconds: default_skip_back_seconds(),
+        skip_forward_seconds: default_skip_forward_seconds(),
+        feed_refresh_concurrency: default_feed_refresh_concurrency(),
+        download_concurrency: default_download_concurrency(),
+        feed_request_timeout_seconds: default_feed_request_timeout_seconds(),
+        user_agent: default_user_agent(),
+        connect_timeout_seconds: default_connect_timeout_seconds(),
+        read_timeout_seconds: default_read_timeout_seconds(),
+        proxy: None,
+        max_redirects: default_max_redirects(),
+    }
+}
+
 pub fn create_config(config: Option<CastironConfig>) -> Result<CastironConfig, CustomError> {
     match config {
         Some(conf) => {
-            let config_file_path = Path::new("./castiron_config.json");
-            let config_file = File::create(config_file_path)?;
+            let config_file = File::create(paths().config_file())?;
             let mut writer = BufWriter::new(config_file);
             to_writer(&mut writer, &conf)?;
             writer.flush()?;
             Ok(conf)
         }
         None => {
-            let conf = CastironConfig {
-                theme: Theme::default().to_string(),
-            };
-            let config_file_path = Path::new("./castiron_config.json");
-            let config_file = File::create(config_file_path)?;
+            let conf = default_config();
+            let config_file = File::create(paths().config_file())?;
             let mut writer = BufWriter::new(config_file);
             to_writer(&mut writer, &conf)?;
             writer.flush()?;
@@ -105,16 +124,35 @@ pub fn convert_theme_string_to_enum(theme: String) -> Theme {
 mod tests {
     use super::*;
 
+    /// The only test that touches the config file, which every test in the process shares.
     #[test]
-    fn test_create_config() {
-        let test_config_value = CastironConfig {
-            theme: Theme::default().to_string(),
-        };
-        assert!(create_config(Some(test_config_value)).is_ok())
+    fn test_create_and_read_config() {
+        let written = create_config(Some(CastironConfig {
+            skip_back_seconds: 42,
+            ..default_config()
+        }))
+        .unwrap();
+        let read = read_config().unwrap();
+        assert_eq!(read.skip_back_seconds, 42);
+        assert_eq!(read.theme, written.theme);
+        assert_eq!(read.user_agent, written.user_agent);
     }
 
     #[test]
-    fn test_read_config() {
-        assert!(read_config().is_ok())
+    fn test_config_without_newer_fields_gets_defaults() {
+        let config: CastironConfig = serde_json::from_str(r#"{"theme":"Dark"}"#).unwrap();
+        assert_eq!(
+            config.mark_played_threshold_seconds,
+            default_mark_played_threshold_seconds()
+        );
+        assert_eq!(config.skip_back_seconds, default_skip_back_seconds());
+        assert_eq!(config.skip_forward_seconds, default_skip_forward_seconds());
+        assert_eq!(
+            config.feed_refresh_concurrency,
+            default_feed_refresh_concurrency()
+        );
+        assert_eq!(config.download_concurrency, default_download_concurrency());
+        assert_eq!(config.user_agent, default_user_agent());
+        assert_eq!(config.proxy, None);
     }
 }
@@ -0,0 +1,187 @@
+use crate::{
+    file_handling::migrations::run_migrations,
+    types::errors::{CustomError, RowError},
+};
+use sqlite::{Connection, ConnectionThreadSafe, State, Value};
+use std::{fmt, ops::Deref, path::Path, str::FromStr, sync::Arc};
+
+/// How long a statement waits on a lock held by another connection before giving up.
+const BUSY_TIMEOUT_MS: usize = 5000;
+
+/// The long-lived handle to the library database, opened once at startup and cloned into
+/// whatever needs it. Clones share the same connection.
+#[derive(Clone)]
+pub struct Store {
+    connection: Arc<ConnectionThreadSafe>,
+}
+
+impl Store {
+    /// Opens the database at `path` in WAL mode and brings its schema up to date.
+    pub fn open<T: AsRef<Path>>(path: T) -> Result<Self, CustomError> {
+        let mut connection = Connection::open_thread_safe(path)?;
+        connection.set_busy_timeout(BUSY_TIMEOUT_MS)?;
+        connection.execute("PRAGMA journal_mode = WAL;")?;
+        run_migrations(&connection)?;
+        Ok(Self {
+            connection: Arc::new(connection),
+        })
+    }
+}
+
+impl Deref for Store {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.connection
+    }
+}
+
+impl fmt::Debug for Store {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Store").finish_non_exhaustive()
+    }
+}
+
+/// Prepares `query`, binds `values` to its `?` parameters in order and steps it to completion.
+pub fn execute_bound(
+    connection: &Connection,
+    query: &str,
+    values: &[Value],
+) -> Result<(), CustomError> {
+    let mut statement = connection.prepare(query)?;
+    statement.bind(values)?;
+    while let State::Row = statement.next()? {}
+    Ok(())
+}
+
+/// Bound counterpart to `Connection::iterate`. The callback receives each row as
+/// `(column, value)` pairs and returns `false` to stop iterating early.
+pub fn iterate_bound<F>(
+    connection: &Connection,
+    query: &str,
+    values: &[Value],
+    mut callback: F,
+) -> Result<(), CustomError>
+where
+    F: FnMut(&[(&str, Option<&str>)]) -> bool,
+{
+    let mut statement = connection.prepare(query)?;
+    statement.bind(values)?;
+    while let State::Row = statement.next()? {
+        let mut row: Vec<(String, Option<String>)> = Vec::new();
+        for (index, column) in statement.column_names().iter().enumerate() {
+            row.push((
+                column.to_owned(),
+                statement.read::<Option<String>, _>(index)?,
+            ));
+        }
+        let borrowed: Vec<(&str, Option<&str>)> = row
+            .iter()
+            .map(|(column, value)| (column.as_str(), value.as_deref()))
+            .collect();
+        if !callback(&borrowed) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// A row as handed out by `iterate_bound`: `(column, value)` pairs, with `None` for NULL.
+pub type Row<'a> = [(&'a str, Option<&'a str>)];
+
+/// Types that can be built from a single result row.
+pub trait FromRow: Sized {
+    fn from_row(row: &Row) -> Result<Self, RowError>;
+}
+
+/// Runs a bound query and maps every row it returns, stopping at the first malformed one.
+pub fn query_rows<T: FromRow>(
+    connection: &Connection,
+    query: &str,
+    values: &[Value],
+) -> Result<Vec<T>, CustomError> {
+    let mut rows: Vec<T> = Vec::new();
+    let mut malformed: Option<RowError> = None;
+    iterate_bound(connection, query, values, |row| match T::from_row(row) {
+        Ok(mapped) => {
+            rows.push(mapped);
+            true
+        }
+        Err(e) => {
+            malformed = Some(e);
+            false
+        }
+    })?;
+    match malformed {
+        Some(e) => Err(e.into()),
+        None => Ok(rows),
+    }
+}
+
+/// The value of `column`, or `None` if it is NULL.
+pub fn optional_text<'a>(row: &Row<'a>, column: &'static str) -> Result<Option<&'a str>, RowError> {
+    row.iter()
+        .find(|val| val.0 == column)
+        .map(|val| val.1)
+        .ok_or(RowError::MissingColumn(column))
+}
+
+pub fn required_text(row: &Row, column: &'static str) -> Result<String, RowError> {
+    optional_text(row, column)?
+        .map(str::to_string)
+        .ok_or(RowError::NullValue(column))
+}
+
+pub fn optional_number<T: FromStr>(row: &Row, column: &'static str) -> Result<Option<T>, RowError> {
+    match optional_text(row, column)? {
+        Some(value) => value.parse().map(Some).map_err(|_| RowError::InvalidValue {
+            column,
+            value: value.to_string(),
+        }),
+        None => Ok(None),
+    }
+}
+
+pub fn required_number<T: FromStr>(row: &Row, column: &'static str) -> Result<T, RowError> {
+    optional_number(row, column)?.ok_or(RowError::NullValue(column))
+}
+
+/// A boolean stored as 0 or 1. NULL reads as `false`, matching rows written before the flag existed.
+pub fn flag(row: &Row, column: &'static str) -> Result<bool, RowError> {
+    Ok(optional_number::<i8>(row, column)?.unwrap_or(0) == 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlite::open;
+
+    #[test]
+    fn test_bound_values_round_trip() {
+        let connection = open(":memory:").unwrap();
+        connection
+            .execute("CREATE TABLE hostile(id INTEGER PRIMARY KEY, body TEXT);")
+            .unwrap();
+        let hostile = "it's a \"quote\"; DROP TABLE hostile; -- ünïcødé 🎙";
+        execute_bound(
+            &connection,
+            "INSERT INTO hostile (body) VALUES (?);",
+            &[hostile.into()],
+        )
+        .unwrap();
+        let mut found: Vec<String> = Vec::new();
+        iterate_bound(
+            &connection,
+            "SELECT * FROM hostile WHERE body = ?;",
+            &[hostile.into()],
+            |row| {
+                if let Some((_, Some(body))) = row.iter().find(|val| val.0 == "body") {
+                    found.push(body.to_string());
+                }
+                true
+            },
+        )
+        .unwrap();
+        assert_eq!(found, vec![hostile.to_string()]);
+    }
+}
@@ -1,9 +1,64 @@
-use crate::types::{episodes::Episode, errors::CustomError};
-use sqlite::{open, Error};
-use std::{fs::remove_file, path::Path};
-use time::{format_description::well_known::Rfc2822, OffsetDateTime};
+use crate::{
+    file_handling::{
+        database::{
+            execute_bound, flag, optional_number, optional_text, query_rows, required_number,
+            FromRow, Row, Store,
+        },
+        library::Library,
+        paths::paths,
+    },
+    parsing::{parse_date, stable_hash},
+    types::{
+        episodes::Episode,
+        errors::{CustomError, RowError},
+    },
+};
+use sqlite::Error;
+use std::fs::remove_file;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+/// How much of the guid is kept, readably, at the start of a file name.
+const MAX_SLUG_CHARS: usize = 48;
+
+/// Where an episode is saved, relative to the episodes directory: a folder per feed holding
+/// a name built from the guid that is safe on any filesystem. Guids are often URLs, so
+/// anything but ASCII letters and digits becomes `-`, and a hash of the full guid keeps
+/// names apart when two guids sanitize to the same text.
+pub fn episode_file_name(feed_id: i32, guid: &str, extension: &str) -> String {
+    let sanitized: String = guid
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let slug: String = sanitized
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+        .chars()
+        .take(MAX_SLUG_CHARS)
+        .collect();
+    let hash = stable_hash(guid);
+    match slug.trim_end_matches('-') {
+        "" => format!("{feed_id}/{hash:016x}.{extension}"),
+        slug => format!("{feed_id}/{slug}-{hash:016x}.{extension}"),
+    }
+}
 
-pub fn add_episode_to_database(episode: Episode) -> Result<(), CustomError> {
+/// Episode dates are stored as RFC 3339 in UTC to the second, so ordering by the text orders
+/// by time. A date that cannot be read becomes the time the episode was first seen, rather
+/// than keeping the episode out of the library.
+pub fn normalize_episode_date(date: &str) -> String {
+    let date = parse_date(date).unwrap_or_else(OffsetDateTime::now_utc);
+    date.replace_nanosecond(0)
+        .unwrap_or(date)
+        .format(&Rfc3339)
+        .unwrap_or_default()
+}
+
+/// Adds an episode, or refreshes the details of one already in the library. What the library
+/// decided about an existing episode (its date, file name and played and downloaded state)
+/// is kept.
+pub fn add_episode_to_database(store: &Store, episode: Episode) -> Result<(), CustomError> {
     let Episode {
         guid,
         title,
@@ -11,35 +66,51 @@ pub fn add_episode_to_database(episode: Episode) -> Result<(), CustomError> {
         file_name,
         url,
         feed_id,
+        description,
+        duration_seconds,
+        size_bytes,
+        season,
+        episode_number,
+        episode_type,
+        link,
+        image_url,
         ..
     } = episode;
-    let mut sanitized_title = title.replace("'", "''");
-    sanitized_title = sanitized_title.replace("\"", "\"\"");
-    let parsed_date = OffsetDateTime::parse(&date, &Rfc2822)?;
-    let connection = open(Path::new("./database.sqlite"))?;
-    let query = format!("
-        CREATE TABLE IF NOT EXISTS episodes(id INTEGER PRIMARY KEY, guid TEXT, title TEXT, date TEXT, played BOOLEAN, played_seconds INTEGER, file_name TEXT, url TEXT, feed_id INTEGER, downloaded BOOLEAN);
-        CREATE UNIQUE INDEX IF NOT EXISTS guid_feed_id ON episodes (guid,feed_id);
-        INSERT INTO episodes (guid, title, date, played, file_name, url, feed_id, downloaded) VALUES ('{guid}', '{sanitized_title}', '{parsed_date}', FALSE, '{file_name}', '{url}', '{feed_id}', FALSE)
-            ON CONFLICT DO NOTHING;
-    ");
-    connection.execute(query)?;
+    execute_bound(
+        store,
+        "INSERT INTO episodes (guid, title, date, played, file_name, url, feed_id, downloaded, description, duration_seconds, size_bytes, season, episode_number, episode_type, link, image_url)
+            VALUES (?, ?, ?, FALSE, ?, ?, ?, FALSE, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (guid, feed_id) DO UPDATE SET description = excluded.description, duration_seconds = excluded.duration_seconds, size_bytes = excluded.size_bytes, season = excluded.season, episode_number = excluded.episode_number, episode_type = excluded.episode_type, link = excluded.link, image_url = excluded.image_url;",
+        &[
+            guid.into(),
+            title.into(),
+            normalize_episode_date(&date).into(),
+            file_name.into(),
+            url.into(),
+            i64::from(feed_id).into(),
+            description.into(),
+            duration_seconds.map(i64::from).into(),
+            size_bytes.and_then(|size| i64::try_from(size).ok()).into(),
+            season.map(i64::from).into(),
+            episode_number.map(i64::from).into(),
+            episode_type.into(),
+            link.into(),
+            image_url.into(),
+        ],
+    )?;
     Ok(())
 }
 
-pub fn get_episode_list_database() -> Result<Vec<Episode>, CustomError> {
-    let connection = open(Path::new("./database.sqlite"))?;
-    let query = "SELECT * FROM episodes ORDER BY date DESC;";
-    let mut episodes: Vec<Episode> = Vec::new();
-    connection.iterate(query, |n| select_all_callback(n, &mut episodes))?;
-    Ok(episodes)
+pub fn get_episode_list_database(store: &Store) -> Result<Vec<Episode>, CustomError> {
+    query_rows(store, "SELECT * FROM episodes ORDER BY date DESC;", &[])
 }
 
-pub fn get_episode_by_id(id: i32) -> Result<Episode, CustomError> {
-    let connection = open(Path::new("./database.sqlite"))?;
-    let query = format!("SELECT * FROM episodes WHERE id = '{id}'");
-    let mut episodes: Vec<Episode> = Vec::new();
-    connection.iterate(query, |n| select_all_callback(n, &mut episodes))?;
+pub fn get_episode_by_id(store: &Store, id: i32) -> Result<Episode, CustomError> {
+    let mut episodes: Vec<Episode> = query_rows(
+        store,
+        "SELECT * FROM episodes WHERE id = ?;",
+        &[i64::from(id).into()],
+    )?;
     match episodes.is_empty() {
         true => Err(CustomError::SqlError(Error {
             code: None,
@@ -49,227 +120,154 @@ pub fn get_episode_by_id(id: i32) -> Result<Episode, CustomError> {
     }
 }
 
-pub fn get_episodes_by_feed_id(feed_id: i32) -> Result<Vec<Episode>, CustomError> {
-    let connection = open(Path::new("./database.sqlite"))?;
-    let query = format!("SELECT * FROM episodes WHERE feed_id = {feed_id} ORDER BY date DESC;");
-    let mut episodes: Vec<Episode> = Vec::new();
-    connection.iterate(query, |n| select_all_callback(n, &mut episodes))?;
-    Ok(episodes)
+pub fn get_episodes_by_feed_id(store: &Store, feed_id: i32) -> Result<Vec<Episode>, CustomError> {
+    query_rows(
+        store,
+        "SELECT * FROM episodes WHERE feed_id = ? ORDER BY date DESC;",
+        &[i64::from(feed_id).into()],
+    )
 }
 
-pub fn update_episode_download_status(id: i32, downloaded: bool) -> Result<(), CustomError> {
-    let connection = open(Path::new("./database.sqlite"))?;
-    let query = format!("UPDATE episodes SET downloaded = {downloaded} WHERE id = '{id}';");
-    connection.execute(query)?;
+pub fn update_episode_file_name(
+    store: &Store,
+    id: i32,
+    file_name: String,
+) -> Result<(), CustomError> {
+    execute_bound(
+        store,
+        "UPDATE episodes SET file_name = ? WHERE id = ?;",
+        &[file_name.into(), i64::from(id).into()],
+    )?;
     Ok(())
 }
 
-pub fn delete_episode_from_fs(id: i32) -> Result<(), CustomError> {
-    let connection = open(Path::new("./database.sqlite"))?;
-    let query = format!("SELECT file_name FROM episodes WHERE id = '{id}'");
-    let mut delete_file_name = String::new();
-    connection.iterate(query, |n| {
-        let result_tuple = n.get(0);
-        match result_tuple {
-            Some(wrapped_file_name) => match wrapped_file_name.1 {
-                Some(file_name) => {
-                    delete_file_name = file_name.to_string();
-                }
-                None => (),
-            },
-            None => (),
-        }
-        true
-    })?;
-    remove_file(Path::new(format!("./episodes/{delete_file_name}").as_str()))?;
-    update_episode_download_status(id, false)?;
+pub fn update_episode_download_status(
+    store: &Store,
+    id: i32,
+    downloaded: bool,
+) -> Result<(), CustomError> {
+    execute_bound(
+        store,
+        "UPDATE episodes SET downloaded = ? WHERE id = ?;",
+        &[i64::from(downloaded).into(), i64::from(id).into()],
+    )?;
+    Ok(())
+}
+
+pub fn update_episode_played_seconds(
+    store: &Store,
+    id: i32,
+    played_seconds: i32,
+) -> Result<(), CustomError> {
+    execute_bound(
+        store,
+        "UPDATE episodes SET played_seconds = ? WHERE id = ?;",
+        &[i64::from(played_seconds).into(), i64::from(id).into()],
+    )?;
+    Ok(())
+}
+
+/// Marking an episode played or unplayed also forgets its playback position, so it starts
+/// from the beginning the next time it is opened.
+pub fn update_episode_played_status(
+    store: &Store,
+    id: i32,
+    played: bool,
+) -> Result<(), CustomError> {
+    execute_bound(
+        store,
+        "UPDATE episodes SET played = ?, played_seconds = 0 WHERE id = ?;",
+        &[i64::from(played).into(), i64::from(id).into()],
+    )?;
+    Ok(())
+}
+
+/// Marking many episodes at once leaves their playback positions alone, so that it can be
+/// undone without losing anyone's place.
+pub fn update_feed_played_status(
+    store: &Store,
+    feed_id: i32,
+    played: bool,
+) -> Result<(), CustomError> {
+    execute_bound(
+        store,
+        "UPDATE episodes SET played = ? WHERE feed_id = ?;",
+        &[i64::from(played).into(), i64::from(feed_id).into()],
+    )?;
+    Ok(())
+}
+
+pub fn update_all_played_status(store: &Store, played: bool) -> Result<(), CustomError> {
+    execute_bound(
+        store,
+        "UPDATE episodes SET played = ?;",
+        &[i64::from(played).into()],
+    )?;
     Ok(())
 }
 
-pub fn mark_episodes_deleted_if_file_nonexistent() -> Result<(), CustomError> {
-    let connection = open(Path::new("./database.sqlite"))?;
-    let query = "SELECT * FROM episodes;";
-    let mut episodes: Vec<Episode> = Vec::new();
-    connection.iterate(query, |n| select_all_callback(n, &mut episodes))?;
-    for episode in episodes {
+pub fn delete_episode_from_fs(library: &dyn Library, id: i32) -> Result<(), CustomError> {
+    let episode = library.get_episode_by_id(id)?;
+    remove_file(paths().episode_file(&episode.file_name))?;
+    library.update_episode_download_status(id, false)?;
+    Ok(())
+}
+
+pub fn mark_episodes_deleted_if_file_nonexistent(library: &dyn Library) -> Result<(), CustomError> {
+    for episode in library.get_episodes()? {
         match (
-            Path::new(format!("./episodes/{}", episode.file_name).as_str()).exists(),
+            paths().episode_file(&episode.file_name).exists(),
             episode.downloaded,
         ) {
             (true, true) => (),
             (false, false) => (),
-            (true, false) => update_episode_download_status(episode.id, true)?,
-            (false, true) => update_episode_download_status(episode.id, false)?,
+            (true, false) => library.update_episode_download_status(episode.id, true)?,
+            (false, true) => library.update_episode_download_status(episode.id, false)?,
         }
     }
     Ok(())
 }
 
-fn select_all_callback(n: &[(&str, Option<&str>)], episodes: &mut Vec<Episode>) -> bool {
-    let mut result_tuple: Episode = Episode {
-        id: 0,
-        guid: String::new(),
-        title: String::new(),
-        date: String::new(),
-        played: false,
-        played_seconds: 0,
-        file_name: String::new(),
-        url: String::new(),
-        feed_id: 0,
-        downloaded: false,
-    };
-    let id_kv_tuple = n.iter().find(|val| val.0 == "id");
-    match id_kv_tuple {
-        Some(wrapped_id) => match wrapped_id.1 {
-            Some(id) => {
-                result_tuple.id = match id.parse::<i32>() {
-                    Ok(parsed) => parsed,
-                    Err(_) => 0,
-                }
-            }
-            None => (),
-        },
-        None => (),
-    }
-    let guid_kv_tuple = n.iter().find(|val| val.0 == "guid");
-    match guid_kv_tuple {
-        Some(wrapped_guid) => match wrapped_guid.1 {
-            Some(guid) => result_tuple.guid = guid.to_string(),
-            None => (),
-        },
-        None => (),
-    }
-    let title_kv_tuple = n.iter().find(|val| val.0 == "title");
-    match title_kv_tuple {
-        Some(wrapped_title) => match wrapped_title.1 {
-            Some(title) => result_tuple.title = title.to_string(),
-            None => (),
-        },
-        None => (),
-    }
-    let date_kv_tuple = n.iter().find(|val| val.0 == "date");
-    match date_kv_tuple {
-        Some(wrapped_date) => match wrapped_date.1 {
-            Some(date) => result_tuple.date = date.to_string(),
-            None => (),
-        },
-        None => (),
-    }
-    let played_kv_tuple = n.iter().find(|val| val.0 == "played");
-    match played_kv_tuple {
-        Some(wrapped_played) => match wrapped_played.1 {
-            Some(played) => {
-                result_tuple.played = match played.parse::<i8>() {
-                    Ok(parsed) => match parsed {
-                        0 => false,
-                        1 => true,
-                        _ => false,
-                    },
-                    Err(_) => false,
-                }
-            }
-            None => (),
-        },
-        None => (),
-    }
-    let played_seconds_kv_tuple = n.iter().find(|val| val.0 == "played_seconds");
-    match played_seconds_kv_tuple {
-        Some(wrapped_played_seconds) => match wrapped_played_seconds.1 {
-            Some(played_seconds) => {
-                result_tuple.played_seconds = played_seconds.parse::<i32>().unwrap()
-            }
-            None => (),
-        },
-        None => (),
-    }
-    let file_path_kv_tuple = n.iter().find(|val| val.0 == "file_name");
-    match file_path_kv_tuple {
-        Some(wrapped_file_path) => match wrapped_file_path.1 {
-            Some(file_path) => result_tuple.file_name = file_path.to_string(),
-            None => (),
-        },
-        None => (),
-    }
-    let url_kv_tuple = n.iter().find(|val| val.0 == "url");
-    match url_kv_tuple {
-        Some(wrapped_url) => match wrapped_url.1 {
-            Some(url) => result_tuple.url = url.to_string(),
-            None => (),
-        },
-        None => (),
+impl FromRow for Episode {
+    fn from_row(row: &Row) -> Result<Self, RowError> {
+        let text =
+            |column| Ok::<_, RowError>(optional_text(row, column)?.unwrap_or_default().to_string());
+        Ok(Episode {
+            id: required_number(row, "id")?,
+            guid: text("guid")?,
+            title: text("title")?,
+            date: text("date")?,
+            played: flag(row, "played")?,
+            played_seconds: optional_number(row, "played_seconds")?.unwrap_or(0),
+            file_name: text("file_name")?,
+            url: text("url")?,
+            feed_id: required_number(row, "feed_id")?,
+            downloaded: flag(row, "downloaded")?,
+            description: optional_text(row, "description")?.map(str::to_string),
+            duration_seconds: optional_number(row, "duration_seconds")?,
+            size_bytes: optional_number(row, "size_bytes")?,
+            season: optional_number(row, "season")?,
+            episode_number: optional_number(row, "episode_number")?,
+            episode_type: optional_text(row, "episode_type")?.map(str::to_string),
+            link: optional_text(row, "link")?.map(str::to_string),
+            image_url: optional_text(row, "image_url")?.map(str::to_string),
+        })
     }
-    let feed_id_kv_tuple = n.iter().find(|val| val.0 == "feed_id");
-    match feed_id_kv_tuple {
-        Some(wrapped_feed_id) => match wrapped_feed_id.1 {
-            Some(feed_id) => result_tuple.feed_id = feed_id.parse::<i32>().unwrap(),
-            None => (),
-        },
-        None => (),
-    }
-    let downloaded_kv_tuple = n.iter().find(|val| val.0 == "downloaded");
-    match downloaded_kv_tuple {
-        Some(wrapped_downloaded) => match wrapped_downloaded.1 {
-            Some(downloaded) => {
-                result_tuple.downloaded = match downloaded.parse::<i8>() {
-                    Ok(parsed) => match parsed {
-                        0 => false,
-                        1 => true,
-                        _ => false,
-                    },
-                    Err(_) => false,
-                }
-            }
-            None => (),
-        },
-        None => (),
-    }
-    episodes.push(result_tuple);
-    true
 }
 
 #[cfg(test)]
 mod tests {
-    use std::fs::{copy, remove_file};
-
     use super::*;
+    use crate::file_handling::memory::InMemoryLibrary;
 
     #[test]
     fn test_add_episode() {
-        if Path::new("./database.sqlite").exists() {
-            let existing_db_file_path = Path::new("./database.sqlite");
-            let new_db_file = Path::new("./temp_db.sqlite");
-            let copy_result = copy(&existing_db_file_path, &new_db_file).is_ok();
-            let delete_old_result = remove_file(&existing_db_file_path).is_ok();
-            if let true = copy_result & delete_old_result {
-                assert!(add_episode_to_database(Episode {
-                    id: 0,
-                    date: String::from("2024/05/30"),
-                    guid: String::from("jkdfjskluizuien1"),
-                    title: String::from("Interesting Show Title"),
-                    url: String::from("https://www.google.com"),
-                    feed_id: 998,
-                    played_seconds: 0,
-                    file_name: String::from("pod.mp3"),
-                    played: false,
-                    downloaded: false
-                })
-                .is_ok());
-                if let true = copy(&new_db_file, &existing_db_file_path).is_ok() {
-                    let result = remove_file(&new_db_file);
-                    match result {
-                        Ok(_) => assert!(true),
-                        Err(_) => panic!("Test failed due to test internals."),
-                    };
-                } else {
-                    panic!("Test failed due to test internals.")
-                }
-            } else {
-                panic!("Test failed due to test internals.")
-            };
-        } else {
-            assert!(add_episode_to_database(Episode {
+        let store = Store::open(":memory:").unwrap();
+        assert!(add_episode_to_database(
+            &store,
+            Episode {
                 id: 0,
-                date: String::from("2024/05/30"),
+                date: String::from("Thu, 30 May 2024 00:00:00 +0000"),
                 guid: String::from("jkdfjskluizuien1"),
                 title: String::from("Interesting Show Title"),
                 url: String::from("https://www.google.com"),
@@ -278,13 +276,174 @@ mod tests {
                 file_name: String::from("pod.mp3"),
                 played: false,
                 downloaded: false,
-            })
-            .is_ok())
+                ..Episode::default()
+            }
+        )
+        .is_ok())
+    }
+
+    #[test]
+    fn test_dates_are_stored_in_utc_or_as_first_seen() {
+        let store = Store::open(":memory:").unwrap();
+        for (guid, date) in [
+            ("offset", "Thu, 30 May 2024 23:30:00 -0200"),
+            ("unreadable", "sometime last week"),
+        ] {
+            add_episode_to_database(
+                &store,
+                Episode {
+                    id: 0,
+                    date: date.to_string(),
+                    guid: guid.to_string(),
+                    title: String::new(),
+                    url: String::from("https://example.com/1.mp3"),
+                    feed_id: 996,
+                    played_seconds: 0,
+                    file_name: format!("{guid}.mp3"),
+                    played: false,
+                    downloaded: false,
+                    ..Episode::default()
+                },
+            )
+            .unwrap();
         }
+        let episodes = get_episodes_by_feed_id(&store, 996).unwrap();
+        let date = |guid: &str| {
+            episodes
+                .iter()
+                .find(|episode| episode.guid == guid)
+                .unwrap()
+                .date
+                .to_owned()
+        };
+        assert_eq!(date("offset"), "2024-05-31T01:30:00Z");
+        let first_seen = OffsetDateTime::parse(&date("unreadable"), &Rfc3339).unwrap();
+        assert!((OffsetDateTime::now_utc() - first_seen).whole_minutes() < 5);
     }
 
     #[test]
     fn test_get_episode_list() {
-        assert!(get_episode_list_database().is_ok())
+        let store = Store::open(":memory:").unwrap();
+        assert!(get_episode_list_database(&store).is_ok())
+    }
+
+    #[test]
+    fn test_hostile_strings_round_trip() {
+        let store = Store::open(":memory:").unwrap();
+        let feed_id = 997;
+        let hostile = "O'Brien's \"Show\"; DROP TABLE episodes; -- ünïcødé 🎙";
+        let guid = format!("https://example.com/?id='1';--&q=\"{hostile}\"");
+        let file_name = format!("{hostile}.mp3");
+        add_episode_to_database(
+            &store,
+            Episode {
+                id: 0,
+                date: String::from("Thu, 30 May 2024 00:00:00 +0000"),
+                guid: guid.to_owned(),
+                title: hostile.to_string(),
+                url: format!("https://example.com/{hostile}.mp3"),
+                feed_id,
+                played_seconds: 0,
+                file_name: file_name.to_owned(),
+                played: false,
+                downloaded: false,
+                ..Episode::default()
+            },
+        )
+        .unwrap();
+        let found = get_episodes_by_feed_id(&store, feed_id)
+            .unwrap()
+            .into_iter()
+            .find(|episode| episode.guid == guid)
+            .unwrap();
+        assert_eq!(found.title, hostile);
+        assert_eq!(found.file_name, file_name);
+        assert_eq!(found.url, format!("https://example.com/{hostile}.mp3"));
+        update_episode_download_status(&store, found.id, true).unwrap();
+        assert!(get_episode_by_id(&store, found.id).unwrap().downloaded);
+    }
+
+    #[test]
+    fn test_malformed_row_is_an_error() {
+        let store = Store::open(":memory:").unwrap();
+        store
+            .execute(
+                "INSERT INTO episodes (guid, feed_id, played_seconds) VALUES ('a', 1, 'half');",
+            )
+            .unwrap();
+        assert!(matches!(
+            get_episode_list_database(&store),
+            Err(CustomError::RowError(RowError::InvalidValue {
+                column: "played_seconds",
+                ..
+            }))
+        ));
+        store
+            .execute("UPDATE episodes SET played_seconds = NULL, feed_id = NULL;")
+            .unwrap();
+        assert!(matches!(
+            get_episode_list_database(&store),
+            Err(CustomError::RowError(RowError::NullValue("feed_id")))
+        ));
+        store.execute("UPDATE episodes SET feed_id = 1;").unwrap();
+        let episode = get_episode_list_database(&store).unwrap().remove(0);
+        assert_eq!(episode.played_seconds, 0);
+        assert_eq!(episode.title, "");
+        assert!(!episode.played);
+    }
+
+    #[test]
+    fn test_mark_episodes_deleted_if_file_nonexistent() {
+        let library = InMemoryLibrary::default();
+        for (guid, downloaded) in [("missing-file", true), ("present-file", false)] {
+            library
+                .add_episode(Episode {
+                    id: 0,
+                    date: String::from("Thu, 30 May 2024 00:00:00 +0000"),
+                    guid: guid.to_string(),
+                    title: guid.to_string(),
+                    url: format!("https://example.com/{guid}.mp3"),
+                    feed_id: 1,
+                    played_seconds: 0,
+                    file_name: format!("{}-{guid}.mp3", std::process::id()),
+                    played: false,
+                    downloaded: false,
+                    ..Episode::default()
+                })
+                .unwrap();
+            let id = library.get_episodes().unwrap().remove(0).id;
+            library
+                .update_episode_download_status(id, downloaded)
+                .unwrap();
+        }
+        std::fs::create_dir_all(paths().episodes_dir()).unwrap();
+        let present = format!("{}-present-file.mp3", std::process::id());
+        std::fs::write(paths().episode_file(&present), b"").unwrap();
+
+        mark_episodes_deleted_if_file_nonexistent(&library).unwrap();
+        for episode in library.get_episodes().unwrap() {
+            assert_eq!(episode.downloaded, episode.guid == "present-file");
+        }
+        remove_file(paths().episode_file(&present)).unwrap();
+    }
+
+    #[test]
+    fn test_episode_file_names_are_safe_and_distinct() {
+        let name = episode_file_name(4, "https://example.com/ep?id=1&x=../../etc", "mp3");
+        assert!(name.starts_with("4/https-example-com-ep-id-1-x-etc-"));
+        assert!(name.ends_with(".mp3"));
+        assert_eq!(name.matches('/').count(), 1);
+        assert!(!name.contains(".."));
+        assert_ne!(
+            episode_file_name(4, "a/b", "mp3"),
+            episode_file_name(4, "a?b", "mp3")
+        );
+        assert_eq!(
+            episode_file_name(4, "a/b", "mp3"),
+            episode_file_name(4, "a/b", "mp3")
+        );
+        assert!(episode_file_name(4, "://", "mp3").starts_with("4/"));
+        let long = episode_file_name(4, &"x".repeat(500), "mp3");
+        assert!(long.len() < 100);
     }
 }
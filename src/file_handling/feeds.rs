@@ -1,188 +1,234 @@
-use crate::types::{errors::CustomError, feeds::FeedMeta};
-use sqlite::open;
+use crate::{
+    file_handling::{
+        database::{
+            execute_bound, iterate_bound, optional_number, optional_text, query_rows,
+            required_number, required_text, FromRow, Row, Store,
+        },
+        library::Library,
+        paths::paths,
+    },
+    types::{
+        errors::{CustomError, RowError},
+        feeds::{FeedCredentials, FeedDetails, FeedMeta},
+    },
+};
+use serde_json::{from_str, to_string};
 use std::{
-    fs::{read_to_string, remove_file},
+    fs::{read_to_string, remove_dir, remove_file},
     io::Error as IOError,
-    path::Path,
+    path::{Path, PathBuf},
 };
 
-pub fn add_feed_to_database(url: String) -> Result<(), CustomError> {
-    let connection = open(Path::new("./database.sqlite"))?;
-    let query = format!("CREATE TABLE IF NOT EXISTS feeds(id INTEGER PRIMARY KEY, url TEXT NOT NULL, xml_file_path TEXT, feed_title TEXT, image_file_path TEXT); INSERT INTO feeds (url) VALUES ('{url}');");
-    connection.execute(query)?;
+pub fn add_feed_to_database(store: &Store, url: String) -> Result<(), CustomError> {
+    execute_bound(store, "INSERT INTO feeds (url) VALUES (?);", &[url.into()])?;
     Ok(())
 }
 
-pub fn update_feed_title(id: i32, title: String) -> Result<(), CustomError> {
-    let connection = open(Path::new("./database.sqlite"))?;
-    let mut sanitized_title = title.replace("'", "''");
-    sanitized_title = sanitized_title.replace("\"", "\"\"");
-    let query = format!("UPDATE feeds SET feed_title = '{sanitized_title}' WHERE id = {id};");
-    connection.execute(query)?;
+pub fn update_feed_title(store: &Store, id: i32, title: String) -> Result<(), CustomError> {
+    execute_bound(
+        store,
+        "UPDATE feeds SET feed_title = ? WHERE id = ?;",
+        &[title.into(), i64::from(id).into()],
+    )?;
     Ok(())
 }
 
-pub fn update_feed_file_path(id: i32, file_path: String) -> Result<(), CustomError> {
-    let connection = open(Path::new("./database.sqlite"))?;
-    let query = format!("UPDATE feeds SET xml_file_path = '{file_path}' WHERE id = {id};");
-    connection.execute(query)?;
+pub fn update_feed_file_path(store: &Store, id: i32, file_path: String) -> Result<(), CustomError> {
+    execute_bound(
+        store,
+        "UPDATE feeds SET xml_file_path = ? WHERE id = ?;",
+        &[file_path.into(), i64::from(id).into()],
+    )?;
     Ok(())
 }
 
-pub fn update_thumbnail_file_path(id: i32, file_path: String) -> Result<(), CustomError> {
-    let connection = open(Path::new("./database.sqlite"))?;
-    let query = format!("UPDATE feeds SET image_file_path = '{file_path}' WHERE id = {id};");
-    connection.execute(query)?;
+pub fn update_thumbnail_file_path(
+    store: &Store,
+    id: i32,
+    file_path: String,
+) -> Result<(), CustomError> {
+    execute_bound(
+        store,
+        "UPDATE feeds SET image_file_path = ? WHERE id = ?;",
+        &[file_path.into(), i64::from(id).into()],
+    )?;
     Ok(())
 }
 
-pub fn get_feed_list_database() -> Result<Vec<FeedMeta>, CustomError> {
-    let connection = open(Path::new("./database.sqlite"))?;
-    let query = "SELECT * FROM feeds";
-    let mut feeds: Vec<FeedMeta> = Vec::new();
-    connection.iterate(query, |n| {
-        let mut result_tuple: FeedMeta = FeedMeta {
-            id: 0,
-            feed_url: String::new(),
-            xml_file_path: None,
-            feed_title: None,
-            image_file_path: None,
-        };
-        let id_kv_tuple = n.iter().find(|val| val.0 == "id");
-        match id_kv_tuple {
-            Some(wrapped_id) => match wrapped_id.1 {
-                Some(id) => result_tuple.id = id.to_string().parse().unwrap(),
-                None => (),
-            },
-            None => (),
-        }
-        let url_kv_tuple = n.iter().find(|val| val.0 == "url");
-        match url_kv_tuple {
-            Some(wrapped_url) => match wrapped_url.1 {
-                Some(url) => result_tuple.feed_url = url.to_string(),
-                None => (),
-            },
-            None => (),
-        }
-        let xml_kv_tuple = n.iter().find(|val| val.0 == "xml_file_path");
-        match xml_kv_tuple {
-            Some(wrapped_xml) => match wrapped_xml.1 {
-                Some(xml) => result_tuple.xml_file_path = Some(xml.to_string()),
-                None => (),
-            },
-            None => (),
-        }
-        let title_kv_pair = n.iter().find(|val| val.0 == "feed_title");
-        match title_kv_pair {
-            Some(title_tuple) => match title_tuple.1 {
-                Some(title) => {
-                    result_tuple.feed_title = Some(title.to_string());
-                }
-                None => (),
+impl FromRow for FeedMeta {
+    fn from_row(row: &Row) -> Result<Self, RowError> {
+        let text = |column| Ok::<_, RowError>(optional_text(row, column)?.map(str::to_string));
+        Ok(FeedMeta {
+            id: required_number(row, "id")?,
+            feed_url: required_text(row, "url")?,
+            xml_file_path: text("xml_file_path")?,
+            feed_title: text("feed_title")?,
+            image_file_path: text("image_file_path")?,
+            playback_speed: optional_number(row, "playback_speed")?,
+            etag: text("etag")?,
+            last_modified: text("last_modified")?,
+            credentials: Some(FeedCredentials {
+                username: text("auth_username")?,
+                password: text("auth_password")?,
+                token_parameter: text("auth_token_parameter")?,
+                token: text("auth_token")?,
+            })
+            .filter(|credentials| !credentials.is_empty()),
+            details: FeedDetails {
+                author: text("author")?,
+                description: text("description")?,
+                website: text("website")?,
+                language: text("language")?,
+                categories: optional_text(row, "categories")?
+                    .and_then(|categories| from_str(categories).ok())
+                    .unwrap_or_default(),
+                explicit: optional_number::<i8>(row, "explicit")?.map(|explicit| explicit == 1),
+                show_type: text("show_type")?,
+                copyright: text("copyright")?,
             },
-            None => (),
-        }
-        if let Some(wrapped_image_file_path) = n.iter().find(|val| val.0 == "image_file_path") {
-            if let Some(image_file_path) = wrapped_image_file_path.1 {
-                result_tuple.image_file_path = Some(image_file_path.to_string());
-            }
-        }
-        feeds.push(result_tuple);
-        true
-    })?;
-    Ok(feeds)
+        })
+    }
+}
+
+pub fn update_feed_playback_speed(store: &Store, id: i32, speed: f32) -> Result<(), CustomError> {
+    execute_bound(
+        store,
+        "UPDATE feeds SET playback_speed = ? WHERE id = ?;",
+        &[f64::from(speed).into(), i64::from(id).into()],
+    )?;
+    Ok(())
+}
+
+pub fn update_feed_cache_headers(
+    store: &Store,
+    id: i32,
+    etag: Option<String>,
+    last_modified: Option<String>,
+) -> Result<(), CustomError> {
+    execute_bound(
+        store,
+        "UPDATE feeds SET etag = ?, last_modified = ? WHERE id = ?;",
+        &[etag.into(), last_modified.into(), i64::from(id).into()],
+    )?;
+    Ok(())
+}
+
+/// Replaces a feed's credentials. `None` makes it a public feed again.
+pub fn update_feed_credentials(
+    store: &Store,
+    id: i32,
+    credentials: Option<FeedCredentials>,
+) -> Result<(), CustomError> {
+    let credentials = credentials.unwrap_or_default();
+    execute_bound(
+        store,
+        "UPDATE feeds SET auth_username = ?, auth_password = ?, auth_token_parameter = ?, auth_token = ? WHERE id = ?;",
+        &[
+            credentials.username.into(),
+            credentials.password.into(),
+            credentials.token_parameter.into(),
+            credentials.token.into(),
+            i64::from(id).into(),
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn update_feed_details(
+    store: &Store,
+    id: i32,
+    details: FeedDetails,
+) -> Result<(), CustomError> {
+    let categories = to_string(&details.categories)?;
+    execute_bound(
+        store,
+        "UPDATE feeds SET author = ?, description = ?, website = ?, language = ?, categories = ?, explicit = ?, show_type = ?, copyright = ? WHERE id = ?;",
+        &[
+            details.author.into(),
+            details.description.into(),
+            details.website.into(),
+            details.language.into(),
+            categories.into(),
+            details.explicit.map(i64::from).into(),
+            details.show_type.into(),
+            details.copyright.into(),
+            i64::from(id).into(),
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn get_feed_list_database(store: &Store) -> Result<Vec<FeedMeta>, CustomError> {
+    query_rows(store, "SELECT * FROM feeds", &[])
 }
 
-pub fn get_feed_by_id(id: i32) -> Result<FeedMeta, CustomError> {
-    let connection = open(Path::new("./database.sqlite"))?;
-    let query = format!("SELECT * FROM feeds WHERE id = {id} LIMIT 1;");
-    let mut result_tuple: FeedMeta = FeedMeta {
+pub fn get_feed_by_id(store: &Store, id: i32) -> Result<FeedMeta, CustomError> {
+    let feeds: Vec<FeedMeta> = query_rows(
+        store,
+        "SELECT * FROM feeds WHERE id = ? LIMIT 1;",
+        &[i64::from(id).into()],
+    )?;
+    Ok(feeds.into_iter().next().unwrap_or(FeedMeta {
         id: 0,
         feed_url: String::new(),
         xml_file_path: None,
         feed_title: None,
         image_file_path: None,
-    };
-    connection.iterate(query, |n| {
-        let id_kv_tuple = n.iter().find(|val| val.0 == "id");
-        match id_kv_tuple {
-            Some(wrapped_id) => match wrapped_id.1 {
-                Some(id) => result_tuple.id = id.to_string().parse().unwrap(),
-                None => (),
-            },
-            None => (),
-        }
-        let url_kv_tuple = n.iter().find(|val| val.0 == "url");
-        match url_kv_tuple {
-            Some(wrapped_url) => match wrapped_url.1 {
-                Some(url) => result_tuple.feed_url = url.to_string(),
-                None => (),
-            },
-            None => (),
-        }
-        let xml_kv_tuple = n.iter().find(|val| val.0 == "xml_file_path");
-        match xml_kv_tuple {
-            Some(wrapped_xml) => match wrapped_xml.1 {
-                Some(xml) => result_tuple.xml_file_path = Some(xml.to_string()),
-                None => (),
-            },
-            None => (),
-        }
-        let title_kv_pair = n.iter().find(|val| val.0 == "feed_title");
-        match title_kv_pair {
-            Some(title_tuple) => match title_tuple.1 {
-                Some(title) => {
-                    result_tuple.feed_title = Some(title.to_string());
-                }
-                None => (),
-            },
-            None => (),
-        }
-        if let Some(wrapped_image_file_path) = n.iter().find(|val| val.0 == "image_file_path") {
-            if let Some(image_file_path) = wrapped_image_file_path.1 {
-                result_tuple.image_file_path = Some(image_file_path.to_string());
+        playback_speed: None,
+        etag: None,
+        last_modified: None,
+        credentials: None,
+        details: FeedDetails::default(),
+    }))
+}
+
+pub fn delete_feed_from_database(store: &Store, id: i32) -> Result<Vec<String>, CustomError> {
+    execute_bound(
+        store,
+        "DELETE FROM feeds WHERE id = ?;",
+        &[i64::from(id).into()],
+    )?;
+    let mut file_names: Vec<String> = Vec::new();
+    iterate_bound(
+        store,
+        "DELETE FROM episodes WHERE feed_id = ? RETURNING file_name;",
+        &[i64::from(id).into()],
+        |row| {
+            if let Some((_, Some(file_name))) = row.first() {
+                file_names.push(file_name.to_string());
             }
-        }
-        true
-    })?;
-    Ok(result_tuple)
+            true
+        },
+    )?;
+    Ok(file_names)
 }
 
 // TODO: update so that it also deletes stored image for feed
-pub fn delete_associated_episodes_and_xml(id: i32) -> Result<(), CustomError> {
-    let connection = open(Path::new("./database.sqlite"))?;
-    let query = format!("SELECT xml_file_path FROM feeds WHERE id = {id};");
-    let mut xml_file_path = String::new();
-    connection.iterate(query, |n| {
-        match n.iter().nth(0) {
-            Some(wrapped_xml_file_path) => match wrapped_xml_file_path.1 {
-                Some(file_path) => xml_file_path = file_path.to_string(),
-                None => (),
-            },
-            None => (),
-        }
-        true
-    })?;
-    remove_file(Path::new(format!("{xml_file_path}").as_str()))?;
-    let query = format!("DELETE FROM feeds WHERE id = {id};");
-    connection.execute(query)?;
-    let query = format!("DELETE FROM episodes WHERE feed_id = {id} RETURNING file_name;");
-    connection.iterate(query, |row| {
-        match row.iter().nth(0) {
-            Some(wrapped_file_name) => match wrapped_file_name.1 {
-                Some(file_name) => {
-                    remove_file(Path::new(format!("./episodes/{file_name}").as_str())).unwrap_or(())
-                }
-                None => (),
-            },
-            None => (),
-        };
-        true
-    })?;
+pub fn delete_associated_episodes_and_xml(
+    library: &dyn Library,
+    id: i32,
+) -> Result<(), CustomError> {
+    // A feed that was never refreshed has no document to remove.
+    remove_file(paths().show_xml_file(id)).unwrap_or(());
+    for file_name in library.delete_feed(id)? {
+        remove_file(paths().episode_file(&file_name)).unwrap_or(())
+    }
+    // Only goes once empty, leaving anything else that was put there alone.
+    remove_dir(paths().feed_episodes_dir(id)).unwrap_or(());
     Ok(())
 }
 
-pub fn load_feed_xml(xml_file_path: String) -> Result<String, IOError> {
+/// Where a feed's thumbnail is, if it has one. It is always looked for in the current
+/// thumbnails directory, so the library keeps working when its directories move; earlier
+/// versions stored a whole path, of which only the file name is used.
+pub fn feed_thumbnail_file(feed: &FeedMeta) -> Option<PathBuf> {
+    feed.image_file_path
+        .as_deref()
+        .and_then(|name| Path::new(name).file_name())
+        .map(|name| paths().thumbnails_dir().join(name))
+}
+
+pub fn load_feed_xml(xml_file_path: &Path) -> Result<String, IOError> {
     let data = read_to_string(xml_file_path)?;
     Ok(data)
 }
@@ -194,16 +240,57 @@ mod tests {
 
     #[test]
     fn test_add_feed() {
+        let store = Store::open(":memory:").unwrap();
         let url = String::from("https://www.google.com");
-        assert!(add_feed_to_database(url).is_ok())
+        assert!(add_feed_to_database(&store, url).is_ok())
     }
 
     #[test]
-    fn test_get_feed_list() {
-        if open(Path::new("./database.sqlite")).is_ok() {
-            assert!(get_feed_list_database().is_ok())
-        } else {
-            assert!(get_feed_list_database().is_err())
+    fn test_hostile_strings_round_trip() {
+        let store = Store::open(":memory:").unwrap();
+        let hostile = "O'Brien's \"Show\"; DROP TABLE feeds; -- ünïcødé 🎙";
+        let url = format!("https://example.com/feed?name='{hostile}'");
+        add_feed_to_database(&store, url.to_owned()).unwrap();
+        let feed = get_feed_list_database(&store)
+            .unwrap()
+            .into_iter()
+            .find(|feed| feed.feed_url == url)
+            .unwrap();
+        update_feed_title(&store, feed.id, hostile.to_string()).unwrap();
+        update_feed_file_path(&store, feed.id, format!("./shows/{hostile}.xml")).unwrap();
+        update_thumbnail_file_path(&store, feed.id, format!("./thumbnails/{hostile}.jpg")).unwrap();
+        let updated = get_feed_by_id(&store, feed.id).unwrap();
+        assert_eq!(updated.feed_url, url);
+        assert_eq!(updated.feed_title, Some(hostile.to_string()));
+        assert_eq!(
+            updated.xml_file_path,
+            Some(format!("./shows/{hostile}.xml"))
+        );
+        assert_eq!(
+            updated.image_file_path,
+            Some(format!("./thumbnails/{hostile}.jpg"))
+        );
+    }
+
+    #[test]
+    fn test_thumbnails_are_found_under_the_current_root() {
+        let store = Store::open(":memory:").unwrap();
+        add_feed_to_database(&store, String::from("https://example.com/feed.xml")).unwrap();
+        let feed = get_feed_list_database(&store).unwrap().remove(0);
+        assert_eq!(feed_thumbnail_file(&feed), None);
+        for stored in ["./thumbnails/3.jpg", "/old/root/thumbnails/3.jpg", "3.jpg"] {
+            update_thumbnail_file_path(&store, feed.id, stored.to_string()).unwrap();
+            assert_eq!(
+                feed_thumbnail_file(&get_feed_by_id(&store, feed.id).unwrap()),
+                Some(paths().thumbnails_dir().join("3.jpg")),
+                "{stored}"
+            );
         }
     }
+
+    #[test]
+    fn test_get_feed_list() {
+        let store = Store::open(":memory:").unwrap();
+        assert!(get_feed_list_database(&store).is_ok())
+    }
 }
@@ -0,0 +1,349 @@
+use crate::{
+    file_handling::{database::Store, episodes, feeds, queue},
+    types::{
+        episodes::Episode,
+        errors::CustomError,
+        feeds::{FeedCredentials, FeedDetails, FeedMeta},
+    },
+};
+use std::fmt;
+
+/// The feed, episode and queue records Castiron keeps. `Store` is the SQLite-backed
+/// implementation the app runs on; tests use the in-memory one in `file_handling::memory`.
+pub trait Library: Send + Sync {
+    fn add_feed(&self, url: String) -> Result<(), CustomError>;
+    fn get_feeds(&self) -> Result<Vec<FeedMeta>, CustomError>;
+    fn get_feed_by_id(&self, id: i32) -> Result<FeedMeta, CustomError>;
+    fn update_feed_title(&self, id: i32, title: String) -> Result<(), CustomError>;
+    fn update_feed_file_path(&self, id: i32, file_path: String) -> Result<(), CustomError>;
+    fn update_thumbnail_file_path(&self, id: i32, file_path: String) -> Result<(), CustomError>;
+    fn update_feed_playback_speed(&self, id: i32, speed: f32) -> Result<(), CustomError>;
+    fn update_feed_cache_headers(
+        &self,
+        id: i32,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    ) -> Result<(), CustomError>;
+    fn update_feed_credentials(
+        &self,
+        id: i32,
+        credentials: Option<FeedCredentials>,
+    ) -> Result<(), CustomError>;
+    fn update_feed_details(&self, id: i32, details: FeedDetails) -> Result<(), CustomError>;
+    /// Removes a feed and its episodes, returning the file names of the removed episodes.
+    fn delete_feed(&self, id: i32) -> Result<Vec<String>, CustomError>;
+
+    /// Adds an episode. If one with the same guid already exists for its feed, only its details
+    /// (description, duration and the like) are updated from `episode`.
+    fn add_episode(&self, episode: Episode) -> Result<(), CustomError>;
+    /// All episodes, newest first.
+    fn get_episodes(&self) -> Result<Vec<Episode>, CustomError>;
+    fn get_episode_by_id(&self, id: i32) -> Result<Episode, CustomError>;
+    fn get_episodes_by_feed_id(&self, feed_id: i32) -> Result<Vec<Episode>, CustomError>;
+    fn update_episode_download_status(&self, id: i32, downloaded: bool) -> Result<(), CustomError>;
+    /// Records the name an episode was actually saved under, once the server has said what it is.
+    fn update_episode_file_name(&self, id: i32, file_name: String) -> Result<(), CustomError>;
+    /// Records how far into an episode playback has got, so it can be resumed later.
+    fn update_played_seconds(&self, id: i32, played_seconds: i32) -> Result<(), CustomError>;
+    /// Marks an episode played or unplayed and resets its playback position.
+    fn update_episode_played_status(&self, id: i32, played: bool) -> Result<(), CustomError>;
+    /// Marks every episode of a feed played or unplayed, keeping their playback positions.
+    fn update_feed_played_status(&self, feed_id: i32, played: bool) -> Result<(), CustomError>;
+    /// Marks every episode played or unplayed, keeping their playback positions.
+    fn update_all_played_status(&self, played: bool) -> Result<(), CustomError>;
+
+    fn save_queue(&self, queue: Vec<i32>) -> Result<(), CustomError>;
+    fn get_queue(&self) -> Result<Vec<Episode>, CustomError>;
+}
+
+impl fmt::Debug for dyn Library {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Library").finish_non_exhaustive()
+    }
+}
+
+impl Library for Store {
+    fn add_feed(&self, url: String) -> Result<(), CustomError> {
+        feeds::add_feed_to_database(self, url)
+    }
+
+    fn get_feeds(&self) -> Result<Vec<FeedMeta>, CustomError> {
+        feeds::get_feed_list_database(self)
+    }
+
+    fn get_feed_by_id(&self, id: i32) -> Result<FeedMeta, CustomError> {
+        feeds::get_feed_by_id(self, id)
+    }
+
+    fn update_feed_title(&self, id: i32, title: String) -> Result<(), CustomError> {
+        feeds::update_feed_title(self, id, title)
+    }
+
+    fn update_feed_file_path(&self, id: i32, file_path: String) -> Result<(), CustomError> {
+        feeds::update_feed_file_path(self, id, file_path)
+    }
+
+    fn update_thumbnail_file_path(&self, id: i32, file_path: String) -> Result<(), CustomError> {
+        feeds::update_thumbnail_file_path(self, id, file_path)
+    }
+
+    fn update_feed_playback_speed(&self, id: i32, speed: f32) -> Result<(), CustomError> {
+        feeds::update_feed_playback_speed(self, id, speed)
+    }
+
+    fn update_feed_cache_headers(
+        &self,
+        id: i32,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    ) -> Result<(), CustomError> {
+        feeds::update_feed_cache_headers(self, id, etag, last_modified)
+    }
+
+    fn update_feed_credentials(
+        &self,
+        id: i32,
+        credentials: Option<FeedCredentials>,
+    ) -> Result<(), CustomError> {
+        feeds::update_feed_credentials(self, id, credentials)
+    }
+
+    fn update_feed_details(&self, id: i32, details: FeedDetails) -> Result<(), CustomError> {
+        feeds::update_feed_details(self, id, details)
+    }
+
+    fn delete_feed(&self, id: i32) -> Result<Vec<String>, CustomError> {
+        feeds::delete_feed_from_database(self, id)
+    }
+
+    fn add_episode(&self, episode: Episode) -> Result<(), CustomError> {
+        episodes::add_episode_to_database(self, episode)
+    }
+
+    fn get_episodes(&self) -> Result<Vec<Episode>, CustomError> {
+        episodes::get_episode_list_database(self)
+    }
+
+    fn get_episode_by_id(&self, id: i32) -> Result<Episode, CustomError> {
+        episodes::get_episode_by_id(self, id)
+    }
+
+    fn get_episodes_by_feed_id(&self, feed_id: i32) -> Result<Vec<Episode>, CustomError> {
+        episodes::get_episodes_by_feed_id(self, feed_id)
+    }
+
+    fn update_episode_download_status(&self, id: i32, downloaded: bool) -> Result<(), CustomError> {
+        episodes::update_episode_download_status(self, id, downloaded)
+    }
+
+    fn update_episode_file_name(&self, id: i32, file_name: String) -> Result<(), CustomError> {
+        episodes::update_episode_file_name(self, id, file_name)
+    }
+
+    fn update_played_seconds(&self, id: i32, played_seconds: i32) -> Result<(), CustomError> {
+        episodes::update_episode_played_seconds(self, id, played_seconds)
+    }
+
+    fn update_episode_played_status(&self, id: i32, played: bool) -> Result<(), CustomError> {
+        episodes::update_episode_played_status(self, id, played)
+    }
+
+    fn update_feed_played_status(&self, feed_id: i32, played: bool) -> Result<(), CustomError> {
+        episodes::update_feed_played_status(self, feed_id, played)
+    }
+
+    fn update_all_played_status(&self, played: bool) -> Result<(), CustomError> {
+        episodes::update_all_played_status(self, played)
+    }
+
+    fn save_queue(&self, queue: Vec<i32>) -> Result<(), CustomError> {
+        queue::save_queue(self, queue)
+    }
+
+    fn get_queue(&self) -> Result<Vec<Episode>, CustomError> {
+        queue::get_queue_database(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_handling::memory::InMemoryLibrary;
+
+    fn episode(guid: &str, date: &str, feed_id: i32) -> Episode {
+        Episode {
+            id: 0,
+            guid: guid.to_string(),
+            title: format!("Title for {guid}"),
+            date: date.to_string(),
+            played: false,
+            played_seconds: 0,
+            file_name: format!("{guid}.mp3"),
+            url: format!("https://example.com/{guid}.mp3"),
+            feed_id,
+            downloaded: false,
+            ..Episode::default()
+        }
+    }
+
+    /// The behaviour every backend has to agree on.
+    fn check_library_contract(library: &dyn Library) {
+        library
+            .add_feed(String::from("https://example.com/feed.xml"))
+            .unwrap();
+        let feed = library.get_feeds().unwrap().remove(0);
+        library
+            .update_feed_title(feed.id, String::from("A Show"))
+            .unwrap();
+        assert_eq!(
+            library.get_feed_by_id(feed.id).unwrap().feed_title,
+            Some(String::from("A Show"))
+        );
+        assert_eq!(
+            library.get_feed_by_id(feed.id).unwrap().playback_speed,
+            None
+        );
+        library.update_feed_playback_speed(feed.id, 1.5).unwrap();
+        assert_eq!(
+            library.get_feed_by_id(feed.id).unwrap().playback_speed,
+            Some(1.5)
+        );
+        library
+            .update_feed_cache_headers(feed.id, Some(String::from("\"abc\"")), None)
+            .unwrap();
+        let cached = library.get_feed_by_id(feed.id).unwrap();
+        assert_eq!(cached.etag, Some(String::from("\"abc\"")));
+        assert_eq!(cached.last_modified, None);
+        assert_eq!(cached.credentials, None);
+        let credentials = FeedCredentials {
+            username: Some(String::from("listener")),
+            password: Some(String::from("hunter2")),
+            ..FeedCredentials::default()
+        };
+        library
+            .update_feed_credentials(feed.id, Some(credentials.to_owned()))
+            .unwrap();
+        assert_eq!(
+            library.get_feed_by_id(feed.id).unwrap().credentials,
+            Some(credentials)
+        );
+        library.update_feed_credentials(feed.id, None).unwrap();
+        assert_eq!(library.get_feed_by_id(feed.id).unwrap().credentials, None);
+        assert_eq!(
+            library.get_feed_by_id(feed.id).unwrap().details,
+            FeedDetails::default()
+        );
+        let details = FeedDetails {
+            author: Some(String::from("The Host")),
+            categories: vec![String::from("Arts › Food"), String::from("Leisure")],
+            explicit: Some(false),
+            show_type: Some(String::from("serial")),
+            ..FeedDetails::default()
+        };
+        library
+            .update_feed_details(feed.id, details.to_owned())
+            .unwrap();
+        assert_eq!(library.get_feed_by_id(feed.id).unwrap().details, details);
+
+        library
+            .add_episode(episode("older", "Wed, 29 May 2024 00:00:00 +0000", feed.id))
+            .unwrap();
+        library
+            .add_episode(episode("newer", "Thu, 30 May 2024 00:00:00 +0000", feed.id))
+            .unwrap();
+        library
+            .add_episode(episode("newer", "Thu, 30 May 2024 00:00:00 +0000", feed.id))
+            .unwrap();
+        let listed = library.get_episodes().unwrap();
+        assert_eq!(
+            listed.iter().map(|e| e.guid.as_str()).collect::<Vec<_>>(),
+            vec!["newer", "older"]
+        );
+        assert_eq!(library.get_episodes_by_feed_id(feed.id).unwrap().len(), 2);
+        library
+            .add_episode(Episode {
+                description: Some(String::from("Notes")),
+                duration_seconds: Some(2712),
+                season: Some(2),
+                ..episode("older", "Fri, 31 May 2024 00:00:00 +0000", feed.id)
+            })
+            .unwrap();
+        let refreshed = library.get_episodes().unwrap().remove(1);
+        assert_eq!(refreshed.guid, "older");
+        assert_eq!(refreshed.description.as_deref(), Some("Notes"));
+        assert_eq!(refreshed.duration_seconds, Some(2712));
+        assert_eq!(refreshed.season, Some(2));
+        assert_eq!(refreshed.episode_number, None);
+        assert!(library
+            .get_episodes_by_feed_id(feed.id + 1)
+            .unwrap()
+            .is_empty());
+
+        let newer = listed[0].to_owned();
+        library
+            .update_episode_download_status(newer.id, true)
+            .unwrap();
+        assert!(library.get_episode_by_id(newer.id).unwrap().downloaded);
+        library
+            .update_episode_file_name(newer.id, String::from("newer.m4a"))
+            .unwrap();
+        assert_eq!(
+            library.get_episode_by_id(newer.id).unwrap().file_name,
+            "newer.m4a"
+        );
+        assert!(library.get_episode_by_id(newer.id + 100).is_err());
+        library.update_played_seconds(newer.id, 1234).unwrap();
+        assert_eq!(
+            library.get_episode_by_id(newer.id).unwrap().played_seconds,
+            1234
+        );
+        library
+            .update_episode_played_status(newer.id, true)
+            .unwrap();
+        let played = library.get_episode_by_id(newer.id).unwrap();
+        assert!(played.played);
+        assert_eq!(played.played_seconds, 0);
+        library.update_played_seconds(newer.id, 1234).unwrap();
+        library
+            .update_feed_played_status(feed.id + 1, true)
+            .unwrap();
+        assert!(!library.get_episodes().unwrap()[1].played);
+        library.update_feed_played_status(feed.id, true).unwrap();
+        assert!(library.get_episodes().unwrap().iter().all(|e| e.played));
+        library.update_all_played_status(false).unwrap();
+        assert!(library.get_episodes().unwrap().iter().all(|e| !e.played));
+        assert_eq!(
+            library.get_episode_by_id(newer.id).unwrap().played_seconds,
+            1234
+        );
+
+        library.save_queue(vec![newer.id]).unwrap();
+        assert_eq!(
+            library
+                .get_queue()
+                .unwrap()
+                .iter()
+                .map(|e| e.id)
+                .collect::<Vec<_>>(),
+            vec![newer.id]
+        );
+        library.save_queue(Vec::new()).unwrap();
+        assert!(library.get_queue().unwrap().is_empty());
+
+        let mut removed = library.delete_feed(feed.id).unwrap();
+        removed.sort();
+        assert_eq!(removed, vec!["newer.m4a", "older.mp3"]);
+        assert!(library.get_feeds().unwrap().is_empty());
+        assert!(library.get_episodes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_sqlite_library_contract() {
+        check_library_contract(&Store::open(":memory:").unwrap());
+    }
+
+    #[test]
+    fn test_in_memory_library_contract() {
+        check_library_contract(&InMemoryLibrary::default());
+    }
+}
@@ -0,0 +1,133 @@
+use url::Url;
+
+/// The extension an episode is saved with for each media type it may be served as. Several
+/// types share an extension because servers disagree on what to call the same format.
+const MEDIA_TYPES: &[(&str, &str)] = &[
+    ("audio/mpeg", "mp3"),
+    ("audio/mp3", "mp3"),
+    ("audio/mpeg3", "mp3"),
+    ("audio/x-mp3", "mp3"),
+    ("audio/x-mpeg", "mp3"),
+    ("audio/x-mpeg-3", "mp3"),
+    ("audio/mp4", "m4a"),
+    ("audio/m4a", "m4a"),
+    ("audio/x-m4a", "m4a"),
+    ("audio/mp4a-latm", "m4a"),
+    ("audio/x-m4b", "m4b"),
+    ("audio/aac", "aac"),
+    ("audio/aacp", "aac"),
+    ("audio/x-aac", "aac"),
+    ("audio/ogg", "oga"),
+    ("audio/vorbis", "oga"),
+    ("audio/x-vorbis+ogg", "oga"),
+    ("application/ogg", "ogg"),
+    ("audio/opus", "opus"),
+    ("audio/flac", "flac"),
+    ("audio/x-flac", "flac"),
+    ("audio/wav", "wav"),
+    ("audio/wave", "wav"),
+    ("audio/x-wav", "wav"),
+    ("audio/vnd.wave", "wav"),
+    ("audio/webm", "weba"),
+    ("audio/x-ms-wma", "wma"),
+    ("audio/aiff", "aiff"),
+    ("audio/x-aiff", "aiff"),
+    ("video/mp4", "mp4"),
+    ("video/x-m4v", "m4v"),
+    ("video/quicktime", "mov"),
+    ("video/webm", "webm"),
+    ("video/ogg", "ogv"),
+    ("video/mpeg", "mpeg"),
+    ("video/x-matroska", "mkv"),
+    ("video/x-msvideo", "avi"),
+];
+
+/// Used when neither the feed nor the URL says what an episode is. MP3 is by far the most
+/// common podcast format, and players sniff the contents anyway.
+pub const FALLBACK_EXTENSION: &str = "mp3";
+
+/// The extension for a media type from a feed or a `Content-Type` header. Parameters and case
+/// are ignored; generic types such as `application/octet-stream` have none.
+pub fn extension_for_mime_type(mime_type: &str) -> Option<&'static str> {
+    let essence = mime_type.split(';').next().unwrap_or_default().trim();
+    MEDIA_TYPES
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(essence))
+        .map(|(_, extension)| *extension)
+}
+
+/// The extension at the end of a URL's path, if it is one used for audio or video.
+pub fn extension_for_url(url: &str) -> Option<&'static str> {
+    let url = Url::parse(url).ok()?;
+    let (_, extension) = url.path_segments()?.next_back()?.rsplit_once('.')?;
+    MEDIA_TYPES
+        .iter()
+        .map(|(_, known)| *known)
+        .find(|known| known.eq_ignore_ascii_case(extension))
+}
+
+/// The extension to save an enclosure with before anything has been downloaded: its declared
+/// type, then its URL, then `FALLBACK_EXTENSION`.
+pub fn enclosure_extension(mime_type: Option<&str>, url: &str) -> &'static str {
+    mime_type
+        .and_then(extension_for_mime_type)
+        .or_else(|| extension_for_url(url))
+        .unwrap_or(FALLBACK_EXTENSION)
+}
+
+/// `file_name` with its extension swapped for `extension`.
+pub fn with_extension(file_name: &str, extension: &str) -> String {
+    let (dir, name) = file_name.rsplit_once('/').unwrap_or(("", file_name));
+    let stem = match name.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem,
+        _ => name,
+    };
+    match dir {
+        "" => format!("{stem}.{extension}"),
+        dir => format!("{dir}/{stem}.{extension}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mime_types_ignore_case_and_parameters() {
+        assert_eq!(extension_for_mime_type("audio/x-m4a"), Some("m4a"));
+        assert_eq!(
+            extension_for_mime_type("Video/MP4; codecs=avc1"),
+            Some("mp4")
+        );
+        assert_eq!(extension_for_mime_type("application/octet-stream"), None);
+        assert_eq!(extension_for_mime_type(""), None);
+    }
+
+    #[test]
+    fn test_enclosure_extension_falls_back_to_the_url() {
+        assert_eq!(
+            enclosure_extension(Some("audio/mp4"), "https://example.com/1.mp3"),
+            "m4a"
+        );
+        assert_eq!(
+            enclosure_extension(None, "https://example.com/show/1.M4A?token=a.mp3"),
+            "m4a"
+        );
+        assert_eq!(
+            enclosure_extension(Some("binary/octet-stream"), "https://example.com/1.opus"),
+            "opus"
+        );
+        assert_eq!(enclosure_extension(None, "https://example.com/play"), "mp3");
+        assert_eq!(
+            enclosure_extension(None, "https://example.com/a.html"),
+            "mp3"
+        );
+    }
+
+    #[test]
+    fn test_with_extension() {
+        assert_eq!(with_extension("4/episode-1.mp3", "m4a"), "4/episode-1.m4a");
+        assert_eq!(with_extension("4/episode", "m4a"), "4/episode.m4a");
+        assert_eq!(with_extension("episode.mp3", "oga"), "episode.oga");
+    }
+}
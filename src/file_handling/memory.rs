@@ -0,0 +1,273 @@
+use crate::{
+    file_handling::{episodes::normalize_episode_date, library::Library},
+    types::{
+        episodes::Episode,
+        errors::CustomError,
+        feeds::{FeedCredentials, FeedDetails, FeedMeta},
+    },
+};
+use sqlite::Error;
+use std::sync::{Mutex, MutexGuard};
+
+/// A `Library` held entirely in memory, so tests can run in parallel without a database file.
+#[derive(Default)]
+pub struct InMemoryLibrary {
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    feeds: Vec<FeedMeta>,
+    episodes: Vec<Episode>,
+    queue: Vec<i32>,
+    last_feed_id: i32,
+    last_episode_id: i32,
+}
+
+impl InMemoryLibrary {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn update_feed<F: FnOnce(&mut FeedMeta)>(&self, id: i32, update: F) -> Result<(), CustomError> {
+        if let Some(feed) = self.state().feeds.iter_mut().find(|feed| feed.id == id) {
+            update(feed);
+        }
+        Ok(())
+    }
+
+    fn update_played<F: Fn(&Episode) -> bool>(
+        &self,
+        filter: F,
+        played: bool,
+    ) -> Result<(), CustomError> {
+        for episode in self.state().episodes.iter_mut().filter(|e| filter(e)) {
+            episode.played = played;
+        }
+        Ok(())
+    }
+}
+
+impl Library for InMemoryLibrary {
+    fn add_feed(&self, url: String) -> Result<(), CustomError> {
+        let mut state = self.state();
+        state.last_feed_id += 1;
+        let id = state.last_feed_id;
+        state.feeds.push(FeedMeta {
+            id,
+            feed_url: url,
+            xml_file_path: None,
+            feed_title: None,
+            image_file_path: None,
+            playback_speed: None,
+            etag: None,
+            last_modified: None,
+            credentials: None,
+            details: FeedDetails::default(),
+        });
+        Ok(())
+    }
+
+    fn get_feeds(&self) -> Result<Vec<FeedMeta>, CustomError> {
+        Ok(self.state().feeds.to_owned())
+    }
+
+    fn get_feed_by_id(&self, id: i32) -> Result<FeedMeta, CustomError> {
+        Ok(self
+            .state()
+            .feeds
+            .iter()
+            .find(|feed| feed.id == id)
+            .cloned()
+            .unwrap_or(FeedMeta {
+                id: 0,
+                feed_url: String::new(),
+                xml_file_path: None,
+                feed_title: None,
+                image_file_path: None,
+                playback_speed: None,
+                etag: None,
+                last_modified: None,
+                credentials: None,
+                details: FeedDetails::default(),
+            }))
+    }
+
+    fn update_feed_title(&self, id: i32, title: String) -> Result<(), CustomError> {
+        self.update_feed(id, |feed| feed.feed_title = Some(title))
+    }
+
+    fn update_feed_file_path(&self, id: i32, file_path: String) -> Result<(), CustomError> {
+        self.update_feed(id, |feed| feed.xml_file_path = Some(file_path))
+    }
+
+    fn update_thumbnail_file_path(&self, id: i32, file_path: String) -> Result<(), CustomError> {
+        self.update_feed(id, |feed| feed.image_file_path = Some(file_path))
+    }
+
+    fn update_feed_playback_speed(&self, id: i32, speed: f32) -> Result<(), CustomError> {
+        self.update_feed(id, |feed| feed.playback_speed = Some(speed))
+    }
+
+    fn update_feed_cache_headers(
+        &self,
+        id: i32,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    ) -> Result<(), CustomError> {
+        self.update_feed(id, |feed| {
+            feed.etag = etag;
+            feed.last_modified = last_modified;
+        })
+    }
+
+    fn update_feed_credentials(
+        &self,
+        id: i32,
+        credentials: Option<FeedCredentials>,
+    ) -> Result<(), CustomError> {
+        self.update_feed(id, |feed| feed.credentials = credentials)
+    }
+
+    fn update_feed_details(&self, id: i32, details: FeedDetails) -> Result<(), CustomError> {
+        self.update_feed(id, |feed| feed.details = details)
+    }
+
+    fn delete_feed(&self, id: i32) -> Result<Vec<String>, CustomError> {
+        let mut state = self.state();
+        state.feeds.retain(|feed| feed.id != id);
+        let (removed, kept): (Vec<Episode>, Vec<Episode>) = state
+            .episodes
+            .drain(..)
+            .partition(|episode| episode.feed_id == id);
+        state.episodes = kept;
+        Ok(removed
+            .into_iter()
+            .map(|episode| episode.file_name)
+            .collect())
+    }
+
+    fn add_episode(&self, episode: Episode) -> Result<(), CustomError> {
+        let mut state = self.state();
+        if let Some(existing) = state
+            .episodes
+            .iter_mut()
+            .find(|existing| existing.guid == episode.guid && existing.feed_id == episode.feed_id)
+        {
+            *existing = Episode {
+                description: episode.description,
+                duration_seconds: episode.duration_seconds,
+                size_bytes: episode.size_bytes,
+                season: episode.season,
+                episode_number: episode.episode_number,
+                episode_type: episode.episode_type,
+                link: episode.link,
+                image_url: episode.image_url,
+                ..existing.to_owned()
+            };
+            return Ok(());
+        }
+        state.last_episode_id += 1;
+        let id = state.last_episode_id;
+        state.episodes.push(Episode {
+            id,
+            date: normalize_episode_date(&episode.date),
+            played: false,
+            downloaded: false,
+            ..episode
+        });
+        Ok(())
+    }
+
+    fn get_episodes(&self) -> Result<Vec<Episode>, CustomError> {
+        let mut episodes = self.state().episodes.to_owned();
+        episodes.sort_by(|a, b| b.date.cmp(&a.date));
+        Ok(episodes)
+    }
+
+    fn get_episode_by_id(&self, id: i32) -> Result<Episode, CustomError> {
+        self.state()
+            .episodes
+            .iter()
+            .find(|episode| episode.id == id)
+            .cloned()
+            .ok_or(CustomError::SqlError(Error {
+                code: None,
+                message: Some(String::from("No episode found.")),
+            }))
+    }
+
+    fn get_episodes_by_feed_id(&self, feed_id: i32) -> Result<Vec<Episode>, CustomError> {
+        Ok(self
+            .get_episodes()?
+            .into_iter()
+            .filter(|episode| episode.feed_id == feed_id)
+            .collect())
+    }
+
+    fn update_episode_download_status(&self, id: i32, downloaded: bool) -> Result<(), CustomError> {
+        if let Some(episode) = self
+            .state()
+            .episodes
+            .iter_mut()
+            .find(|episode| episode.id == id)
+        {
+            episode.downloaded = downloaded;
+        }
+        Ok(())
+    }
+
+    fn update_episode_file_name(&self, id: i32, file_name: String) -> Result<(), CustomError> {
+        if let Some(episode) = self
+            .state()
+            .episodes
+            .iter_mut()
+            .find(|episode| episode.id == id)
+        {
+            episode.file_name = file_name;
+        }
+        Ok(())
+    }
+
+    fn update_played_seconds(&self, id: i32, played_seconds: i32) -> Result<(), CustomError> {
+        if let Some(episode) = self
+            .state()
+            .episodes
+            .iter_mut()
+            .find(|episode| episode.id == id)
+        {
+            episode.played_seconds = played_seconds;
+        }
+        Ok(())
+    }
+
+    fn update_episode_played_status(&self, id: i32, played: bool) -> Result<(), CustomError> {
+        self.update_played(|episode| episode.id == id, played)?;
+        self.update_played_seconds(id, 0)
+    }
+
+    fn update_feed_played_status(&self, feed_id: i32, played: bool) -> Result<(), CustomError> {
+        self.update_played(|episode| episode.feed_id == feed_id, played)
+    }
+
+    fn update_all_played_status(&self, played: bool) -> Result<(), CustomError> {
+        self.update_played(|_| true, played)
+    }
+
+    fn save_queue(&self, queue: Vec<i32>) -> Result<(), CustomError> {
+        self.state().queue = queue;
+        Ok(())
+    }
+
+    fn get_queue(&self) -> Result<Vec<Episode>, CustomError> {
+        let state = self.state();
+        Ok(state
+            .episodes
+            .iter()
+            .filter(|episode| state.queue.contains(&episode.id))
+            .cloned()
+            .collect())
+    }
+}
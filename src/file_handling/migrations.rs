@@ -0,0 +1,487 @@
+use crate::{
+    file_handling::{
+        database::{execute_bound, iterate_bound},
+        paths::paths,
+    },
+    types::errors::CustomError,
+};
+use sqlite::Connection;
+use std::{
+    fs::{create_dir_all, rename},
+    path::{Component, Path},
+};
+use time::{
+    format_description::{self, well_known::Rfc3339},
+    OffsetDateTime, UtcOffset,
+};
+
+/// A schema change, or a change to the library's files that has to happen along with one.
+enum Migration {
+    Sql(&'static str),
+    Rust(fn(&Connection) -> Result<(), CustomError>),
+}
+
+/// Ordered schema changes. Entry `n` upgrades a database from version `n` to `n + 1`, so new
+/// migrations are only ever appended to the end of this list.
+const MIGRATIONS: &[Migration] = &[
+    // 1: initial schema. Uses IF NOT EXISTS so libraries created before versioning adopt it as-is.
+    Migration::Sql(
+        "
+    CREATE TABLE IF NOT EXISTS feeds(id INTEGER PRIMARY KEY, url TEXT NOT NULL, xml_file_path TEXT, feed_title TEXT, image_file_path TEXT);
+    CREATE TABLE IF NOT EXISTS episodes(id INTEGER PRIMARY KEY, guid TEXT, title TEXT, date TEXT, played BOOLEAN, played_seconds INTEGER, file_name TEXT, url TEXT, feed_id INTEGER, downloaded BOOLEAN);
+    CREATE UNIQUE INDEX IF NOT EXISTS guid_feed_id ON episodes (guid,feed_id);
+    CREATE TABLE IF NOT EXISTS queue(id INTEGER PRIMARY KEY, episodes JSON);
+    ",
+    ),
+    // 2: per-feed default playback speed. NULL means normal speed.
+    Migration::Sql("ALTER TABLE feeds ADD COLUMN playback_speed REAL;"),
+    // 3: HTTP validators from the last successful refresh, for conditional requests.
+    Migration::Sql(
+        "
+    ALTER TABLE feeds ADD COLUMN etag TEXT;
+    ALTER TABLE feeds ADD COLUMN last_modified TEXT;
+    ",
+    ),
+    // 4: credentials for private feeds.
+    Migration::Sql(
+        "
+    ALTER TABLE feeds ADD COLUMN auth_username TEXT;
+    ALTER TABLE feeds ADD COLUMN auth_password TEXT;
+    ALTER TABLE feeds ADD COLUMN auth_token_parameter TEXT;
+    ALTER TABLE feeds ADD COLUMN auth_token TEXT;
+    ",
+    ),
+    // 5: episodes move from `{guid}.{ext}` to per-feed folders with sanitized names.
+    Migration::Rust(rename_episode_files),
+    // 6: episode dates go from `OffsetDateTime`'s display form, in the feed's own offset, to
+    // RFC 3339 in UTC, which sorts correctly as text.
+    Migration::Rust(normalize_episode_dates),
+    // 7: episode details beyond what is needed to download and play it. Like 8, clears the
+    // validators so existing episodes get them on the next refresh.
+    Migration::Sql(
+        "
+    ALTER TABLE episodes ADD COLUMN description TEXT;
+    ALTER TABLE episodes ADD COLUMN duration_seconds INTEGER;
+    ALTER TABLE episodes ADD COLUMN size_bytes INTEGER;
+    ALTER TABLE episodes ADD COLUMN season INTEGER;
+    ALTER TABLE episodes ADD COLUMN episode_number INTEGER;
+    ALTER TABLE episodes ADD COLUMN episode_type TEXT;
+    ALTER TABLE episodes ADD COLUMN link TEXT;
+    ALTER TABLE episodes ADD COLUMN image_url TEXT;
+    UPDATE feeds SET etag = NULL, last_modified = NULL;
+    ",
+    ),
+    // 8: show details. `categories` is a JSON array of strings. The validators are cleared so
+    // the next refresh downloads every feed in full instead of getting a 304 and never filling
+    // the new columns in.
+    Migration::Sql(
+        "
+    ALTER TABLE feeds ADD COLUMN author TEXT;
+    ALTER TABLE feeds ADD COLUMN description TEXT;
+    ALTER TABLE feeds ADD COLUMN website TEXT;
+    ALTER TABLE feeds ADD COLUMN language TEXT;
+    ALTER TABLE feeds ADD COLUMN categories JSON;
+    ALTER TABLE feeds ADD COLUMN explicit BOOLEAN;
+    ALTER TABLE feeds ADD COLUMN show_type TEXT;
+    ALTER TABLE feeds ADD COLUMN copyright TEXT;
+    UPDATE feeds SET etag = NULL, last_modified = NULL;
+    ",
+    ),
+];
+
+/// Extensions episodes were saved with before migration 5. Anything else on an old name was
+/// part of the guid, not the file type.
+const KNOWN_EXTENSIONS: &[&str] = &["aac", "mp3", "oga", "opus", "wav", "weba"];
+
+/// The name migration 5 gives an episode. This is a copy of how `episode_file_name` named
+/// files when the migration was written, kept here so that later changes to naming do not
+/// change what an old library is migrated to.
+fn safe_episode_file_name(feed_id: i32, guid: &str, extension: &str) -> String {
+    let sanitized: String = guid
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let slug: String = sanitized
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+        .chars()
+        .take(48)
+        .collect();
+    // 64-bit FNV-1a.
+    let hash = guid.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    match slug.trim_end_matches('-') {
+        "" => format!("{feed_id}/{hash:016x}.{extension}"),
+        slug => format!("{feed_id}/{slug}-{hash:016x}.{extension}"),
+    }
+}
+
+/// Moves downloaded (and partly downloaded) episodes to the names `safe_episode_file_name`
+/// gives them and records the new names. Files are moved before the transaction commits, so
+/// a rolled-back attempt can leave a file at its new name while the database still has the
+/// old one. Each file is therefore only moved when it is at the old path and nothing is at the
+/// new one, and running this again picks up where the failed attempt left off.
+fn rename_episode_files(connection: &Connection) -> Result<(), CustomError> {
+    let mut episodes: Vec<(i64, i32, String, String)> = Vec::new();
+    iterate_bound(
+        connection,
+        "SELECT id, feed_id, guid, file_name FROM episodes;",
+        &[],
+        |row| {
+            if let [(_, Some(id)), (_, Some(feed_id)), (_, Some(guid)), (_, file_name)] = row {
+                if let (Ok(id), Ok(feed_id)) = (id.parse(), feed_id.parse()) {
+                    episodes.push((
+                        id,
+                        feed_id,
+                        guid.to_string(),
+                        file_name.unwrap_or_default().to_string(),
+                    ));
+                }
+            }
+            true
+        },
+    )?;
+    for (id, feed_id, guid, old_name) in episodes {
+        let extension = Path::new(&old_name)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .filter(|extension| KNOWN_EXTENSIONS.contains(extension))
+            .unwrap_or("mp3");
+        let new_name = safe_episode_file_name(feed_id, &guid, extension);
+        if new_name == old_name {
+            continue;
+        }
+        if is_plain_relative(&old_name) {
+            for suffix in ["", ".part"] {
+                let from = paths().episode_file(&format!("{old_name}{suffix}"));
+                let to = paths().episode_file(&format!("{new_name}{suffix}"));
+                if from.is_file() && !to.exists() {
+                    create_dir_all(paths().feed_episodes_dir(feed_id))?;
+                    rename(from, to)?;
+                }
+            }
+        }
+        execute_bound(
+            connection,
+            "UPDATE episodes SET file_name = ? WHERE id = ?;",
+            &[new_name.into(), id.into()],
+        )?;
+    }
+    Ok(())
+}
+
+/// How versions before migration 6 stored dates: `OffsetDateTime`'s display form, such as
+/// `2024-05-30 23:30:00.0 -02:00:00`. Read with its own description rather than `parse_date`,
+/// so the migration keeps doing the same thing however the feed date parser changes.
+const STORED_DATE_FORMAT: &str = "[year]-[month]-[day] [hour padding:none]:[minute]:[second].[subsecond] [offset_hour sign:mandatory]:[offset_minute]:[offset_second]";
+
+/// Dates that cannot be read are left as they are rather than replaced with today's.
+fn normalize_episode_dates(connection: &Connection) -> Result<(), CustomError> {
+    let stored_format = format_description::parse(STORED_DATE_FORMAT)?;
+    let mut dates: Vec<(i64, String)> = Vec::new();
+    iterate_bound(connection, "SELECT id, date FROM episodes;", &[], |row| {
+        if let [(_, Some(id)), (_, Some(date))] = row {
+            if let Ok(id) = id.parse() {
+                dates.push((id, date.to_string()));
+            }
+        }
+        true
+    })?;
+    for (id, date) in dates {
+        let Some(normalized) = OffsetDateTime::parse(&date, &stored_format)
+            .ok()
+            .and_then(|date| date.to_offset(UtcOffset::UTC).replace_nanosecond(0).ok())
+            .and_then(|date| date.format(&Rfc3339).ok())
+        else {
+            continue;
+        };
+        execute_bound(
+            connection,
+            "UPDATE episodes SET date = ? WHERE id = ?;",
+            &[normalized.into(), id.into()],
+        )?;
+    }
+    Ok(())
+}
+
+/// Old names came straight from guids, so only ones that stay inside the episodes directory
+/// are followed.
+fn is_plain_relative(file_name: &str) -> bool {
+    !file_name.is_empty()
+        && Path::new(file_name)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// The schema version this binary writes.
+pub fn latest_schema_version() -> i64 {
+    MIGRATIONS.len() as i64
+}
+
+pub fn get_schema_version(connection: &Connection) -> Result<i64, CustomError> {
+    connection.execute("CREATE TABLE IF NOT EXISTS schema_version(version INTEGER NOT NULL);")?;
+    let mut version: i64 = 0;
+    iterate_bound(
+        connection,
+        "SELECT MAX(version) AS version FROM schema_version;",
+        &[],
+        |n| {
+            if let Some((_, Some(found))) = n.first() {
+                version = found.parse().unwrap_or(0);
+            }
+            true
+        },
+    )?;
+    Ok(version)
+}
+
+/// Applies every migration newer than the database's recorded version, each in its own
+/// transaction. Refuses to touch a database written by a newer build.
+pub fn run_migrations(connection: &Connection) -> Result<(), CustomError> {
+    migrate_to(connection, latest_schema_version())
+}
+
+/// Applies the migrations between the database's recorded version and `target`.
+fn migrate_to(connection: &Connection, target: i64) -> Result<(), CustomError> {
+    let current = get_schema_version(connection)?;
+    if current > latest_schema_version() {
+        return Err(CustomError::UnsupportedSchemaVersion(current));
+    }
+    for (index, migration) in MIGRATIONS
+        .iter()
+        .enumerate()
+        .take(target as usize)
+        .skip(current as usize)
+    {
+        let version = index as i64 + 1;
+        connection.execute("BEGIN;")?;
+        let applied = match migration {
+            Migration::Sql(sql) => connection.execute(sql).map_err(CustomError::from),
+            Migration::Rust(step) => step(connection),
+        }
+        .and_then(|_| {
+            connection.execute("DELETE FROM schema_version;")?;
+            execute_bound(
+                connection,
+                "INSERT INTO schema_version (version) VALUES (?);",
+                &[version.into()],
+            )
+        });
+        match applied {
+            Ok(_) => connection.execute("COMMIT;")?,
+            Err(e) => {
+                connection.execute("ROLLBACK;")?;
+                return Err(e);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlite::open;
+
+    #[test]
+    fn test_migrations_reach_latest_version() {
+        let connection = open(":memory:").unwrap();
+        run_migrations(&connection).unwrap();
+        assert_eq!(
+            get_schema_version(&connection).unwrap(),
+            latest_schema_version()
+        );
+        run_migrations(&connection).unwrap();
+        assert_eq!(
+            get_schema_version(&connection).unwrap(),
+            latest_schema_version()
+        );
+    }
+
+    #[test]
+    fn test_migrations_adopt_unversioned_database() {
+        let connection = open(":memory:").unwrap();
+        connection
+            .execute("CREATE TABLE feeds(id INTEGER PRIMARY KEY, url TEXT NOT NULL, xml_file_path TEXT, feed_title TEXT, image_file_path TEXT); INSERT INTO feeds (url) VALUES ('https://example.com/feed.xml');")
+            .unwrap();
+        run_migrations(&connection).unwrap();
+        let mut urls: Vec<String> = Vec::new();
+        connection
+            .iterate("SELECT url FROM feeds;", |n| {
+                if let Some((_, Some(url))) = n.first() {
+                    urls.push(url.to_string());
+                }
+                true
+            })
+            .unwrap();
+        assert_eq!(urls, vec![String::from("https://example.com/feed.xml")]);
+    }
+
+    #[test]
+    fn test_migrations_move_episodes_to_safe_names() {
+        let connection = open(":memory:").unwrap();
+        connection
+            .execute("CREATE TABLE episodes(id INTEGER PRIMARY KEY, guid TEXT, title TEXT, date TEXT, played BOOLEAN, played_seconds INTEGER, file_name TEXT, url TEXT, feed_id INTEGER, downloaded BOOLEAN);")
+            .unwrap();
+        let downloaded = "migration-test-episode";
+        let escaping = "../migration-test-escape";
+        // Moved by an earlier attempt that was rolled back, so its row still has the old name.
+        let resumed = "migration-test-resumed";
+        for guid in [downloaded, escaping, resumed] {
+            let extension = if guid == downloaded { "oga" } else { "mp3" };
+            execute_bound(
+                &connection,
+                "INSERT INTO episodes (guid, file_name, feed_id) VALUES (?, ?, 7);",
+                &[guid.into(), format!("{guid}.{extension}").into()],
+            )
+            .unwrap();
+        }
+        create_dir_all(paths().feed_episodes_dir(7)).unwrap();
+        std::fs::write(paths().episode_file(&format!("{downloaded}.oga")), "audio").unwrap();
+        let resumed_name = "7/migration-test-resumed-2a0b935b90c1bed2.mp3";
+        std::fs::write(paths().episode_file(resumed_name), "moved").unwrap();
+
+        run_migrations(&connection).unwrap();
+
+        let mut names: Vec<String> = Vec::new();
+        connection
+            .iterate("SELECT file_name FROM episodes ORDER BY id;", |n| {
+                if let Some((_, Some(name))) = n.first() {
+                    names.push(name.to_string());
+                }
+                true
+            })
+            .unwrap();
+        assert_eq!(
+            names,
+            vec![
+                "7/migration-test-episode-18c79543b4f5a7ca.oga",
+                "7/migration-test-escape-b0d04bf26c00609f.mp3",
+                resumed_name,
+            ]
+        );
+        assert!(!paths().episode_file(&format!("{downloaded}.oga")).exists());
+        for (name, contents) in [(&names[0], "audio"), (&names[2], "moved")] {
+            assert_eq!(
+                std::fs::read_to_string(paths().episode_file(name)).unwrap(),
+                contents
+            );
+            std::fs::remove_file(paths().episode_file(name)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_migrations_normalize_episode_dates() {
+        let connection = open(":memory:").unwrap();
+        connection
+            .execute("CREATE TABLE episodes(id INTEGER PRIMARY KEY, guid TEXT, title TEXT, date TEXT, played BOOLEAN, played_seconds INTEGER, file_name TEXT, url TEXT, feed_id INTEGER, downloaded BOOLEAN);")
+            .unwrap();
+        // How earlier versions stored `Thu, 30 May 2024 23:30:00 -0200` and a day earlier in UTC.
+        let stored = [
+            time::OffsetDateTime::parse(
+                "Thu, 30 May 2024 23:30:00 -0200",
+                &time::format_description::well_known::Rfc2822,
+            )
+            .unwrap()
+            .to_string(),
+            time::OffsetDateTime::parse(
+                "Wed, 29 May 2024 09:00:00 +0000",
+                &time::format_description::well_known::Rfc2822,
+            )
+            .unwrap()
+            .to_string(),
+        ];
+        let unreadable = String::from("sometime");
+        for (guid, date) in [
+            ("late", &stored[0]),
+            ("early", &stored[1]),
+            ("unreadable", &unreadable),
+        ] {
+            execute_bound(
+                &connection,
+                "INSERT INTO episodes (guid, date, file_name, feed_id) VALUES (?, ?, ?, 1);",
+                &[
+                    guid.into(),
+                    date.as_str().into(),
+                    format!("{guid}.mp3").into(),
+                ],
+            )
+            .unwrap();
+        }
+
+        run_migrations(&connection).unwrap();
+
+        let mut dates: Vec<String> = Vec::new();
+        connection
+            .iterate("SELECT date FROM episodes ORDER BY date DESC;", |n| {
+                if let Some((_, Some(date))) = n.first() {
+                    dates.push(date.to_string());
+                }
+                true
+            })
+            .unwrap();
+        assert_eq!(
+            dates,
+            vec!["sometime", "2024-05-31T01:30:00Z", "2024-05-29T09:00:00Z"]
+        );
+    }
+
+    /// The etag and last-modified values stored for every feed.
+    fn feed_validators(connection: &Connection) -> Vec<(Option<String>, Option<String>)> {
+        let mut validators = Vec::new();
+        connection
+            .iterate("SELECT etag, last_modified FROM feeds ORDER BY id;", |n| {
+                if let [(_, etag), (_, last_modified)] = n {
+                    validators.push((etag.map(str::to_string), last_modified.map(str::to_string)));
+                }
+                true
+            })
+            .unwrap();
+        validators
+    }
+
+    #[test]
+    fn test_migrations_clear_validators_for_episode_details() {
+        let connection = open(":memory:").unwrap();
+        migrate_to(&connection, 6).unwrap();
+        connection
+            .execute("INSERT INTO feeds (url, etag, last_modified) VALUES ('https://example.com/feed.xml', '\"abc\"', 'Thu, 30 May 2024 13:45:00 GMT');")
+            .unwrap();
+
+        migrate_to(&connection, 7).unwrap();
+
+        assert_eq!(feed_validators(&connection), vec![(None, None)]);
+    }
+
+    #[test]
+    fn test_migrations_clear_validators_for_show_details() {
+        let connection = open(":memory:").unwrap();
+        migrate_to(&connection, 7).unwrap();
+        connection
+            .execute("INSERT INTO feeds (url, etag, last_modified) VALUES ('https://example.com/feed.xml', '\"abc\"', 'Thu, 30 May 2024 13:45:00 GMT');")
+            .unwrap();
+
+        run_migrations(&connection).unwrap();
+
+        assert_eq!(feed_validators(&connection), vec![(None, None)]);
+    }
+
+    #[test]
+    fn test_migrations_refuse_newer_database() {
+        let connection = open(":memory:").unwrap();
+        run_migrations(&connection).unwrap();
+        execute_bound(
+            &connection,
+            "UPDATE schema_version SET version = ?;",
+            &[(latest_schema_version() + 1).into()],
+        )
+        .unwrap();
+        assert!(matches!(
+            run_migrations(&connection),
+            Err(CustomError::UnsupportedSchemaVersion(_))
+        ));
+    }
+}
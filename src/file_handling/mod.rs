@@ -1,6 +1,13 @@
 // TODO: Throughout mods here, consider fs::canonicalize for better cross-platform
 pub mod config;
+pub mod database;
 pub mod episodes;
 pub mod feeds;
+pub mod library;
+pub mod media_types;
+#[cfg(test)]
+pub mod memory;
+pub mod migrations;
+pub mod paths;
 pub mod queue;
 pub mod setup;
@@ -0,0 +1,228 @@
+use std::{
+    env::{current_exe, var_os},
+    ffi::OsString,
+    fs::{create_dir_all, read_dir, remove_dir_all},
+    path::PathBuf,
+    sync::OnceLock,
+    time::Duration,
+};
+
+const APP_DIR: &str = "castiron";
+
+/// Where Castiron keeps its files on disk.
+///
+/// Resolved once per process, in order of preference:
+/// 1. `$CASTIRON_HOME`, holding everything in a single directory.
+/// 2. The XDG base directories: library data under `$XDG_DATA_HOME`, the config file under
+///    `$XDG_CONFIG_HOME` and re-downloadable feed XML and thumbnails under `$XDG_CACHE_HOME`,
+///    each defaulting to its usual location under `$HOME`.
+/// 3. Portable mode, holding everything next to the executable.
+#[derive(Debug, Clone)]
+pub struct Paths {
+    data: PathBuf,
+    config: PathBuf,
+    cache: PathBuf,
+}
+
+impl Paths {
+    pub fn single_root(root: PathBuf) -> Self {
+        Self {
+            data: root.to_owned(),
+            config: root.to_owned(),
+            cache: root,
+        }
+    }
+
+    fn resolve(
+        castiron_home: Option<OsString>,
+        xdg_data_home: Option<OsString>,
+        xdg_config_home: Option<OsString>,
+        xdg_cache_home: Option<OsString>,
+        home: Option<OsString>,
+    ) -> Self {
+        let non_empty =
+            |value: Option<OsString>| value.filter(|v| !v.is_empty()).map(PathBuf::from);
+        if let Some(root) = non_empty(castiron_home) {
+            return Self::single_root(root);
+        }
+        let home = non_empty(home);
+        let xdg_dir = |value: Option<OsString>, fallback: &str| {
+            non_empty(value)
+                .or_else(|| home.as_ref().map(|h| h.join(fallback)))
+                .map(|dir| dir.join(APP_DIR))
+        };
+        match (
+            xdg_dir(xdg_data_home, ".local/share"),
+            xdg_dir(xdg_config_home, ".config"),
+            xdg_dir(xdg_cache_home, ".cache"),
+        ) {
+            (Some(data), Some(config), Some(cache)) => Self {
+                data,
+                config,
+                cache,
+            },
+            _ => Self::single_root(portable_root()),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        Self::resolve(
+            var_os("CASTIRON_HOME"),
+            var_os("XDG_DATA_HOME"),
+            var_os("XDG_CONFIG_HOME"),
+            var_os("XDG_CACHE_HOME"),
+            var_os("HOME"),
+        )
+    }
+
+    pub fn database(&self) -> PathBuf {
+        self.data.join("database.sqlite")
+    }
+
+    pub fn episodes_dir(&self) -> PathBuf {
+        self.data.join("episodes")
+    }
+
+    /// Where a feed's downloaded episodes are kept.
+    pub fn feed_episodes_dir(&self, feed_id: i32) -> PathBuf {
+        self.episodes_dir().join(feed_id.to_string())
+    }
+
+    pub fn episode_file(&self, file_name: &str) -> PathBuf {
+        self.episodes_dir().join(file_name)
+    }
+
+    pub fn config_dir(&self) -> PathBuf {
+        self.config.to_owned()
+    }
+
+    pub fn config_file(&self) -> PathBuf {
+        self.config.join("castiron_config.json")
+    }
+
+    pub fn shows_dir(&self) -> PathBuf {
+        self.cache.join("shows")
+    }
+
+    pub fn show_xml_file(&self, feed_id: i32) -> PathBuf {
+        self.shows_dir().join(format!("{feed_id}.xml"))
+    }
+
+    pub fn thumbnails_dir(&self) -> PathBuf {
+        self.cache.join("thumbnails")
+    }
+
+    pub fn thumbnail_file(&self, feed_id: i32, file_extension: &str) -> PathBuf {
+        self.thumbnails_dir()
+            .join(format!("{feed_id}.{file_extension}"))
+    }
+
+    /// Where artwork for a feed's individual episodes is cached.
+    pub fn episode_thumbnails_dir(&self, feed_id: i32) -> PathBuf {
+        self.thumbnails_dir().join(feed_id.to_string())
+    }
+}
+
+fn portable_root() -> PathBuf {
+    current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.to_path_buf()))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// The process-wide paths. Tests get a scratch directory so they never touch a real library.
+pub fn paths() -> &'static Paths {
+    static PATHS: OnceLock<Paths> = OnceLock::new();
+    PATHS.get_or_init(|| {
+        if cfg!(test) {
+            Paths::single_root(test_root())
+        } else {
+            Paths::from_env()
+        }
+    })
+}
+
+/// Scratch directories untouched for this long belong to test runs that have finished.
+const STALE_TEST_ROOT: Duration = Duration::from_secs(60 * 60);
+
+/// A fresh scratch directory for this test process. Statics are never dropped, so there is
+/// no point at which a run could remove its own; instead each run clears out what finished
+/// runs left behind.
+fn test_root() -> PathBuf {
+    let temp = std::env::temp_dir();
+    for entry in read_dir(&temp).into_iter().flatten().flatten() {
+        let stale = entry
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > STALE_TEST_ROOT);
+        if stale
+            && entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with("castiron-test-")
+        {
+            remove_dir_all(entry.path()).unwrap_or(());
+        }
+    }
+    let root = temp.join(format!("castiron-test-{}", std::process::id()));
+    // Left over from an earlier process that had the same id.
+    remove_dir_all(&root).unwrap_or(());
+    create_dir_all(&root).unwrap_or(());
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_castiron_home_takes_precedence() {
+        let resolved = Paths::resolve(
+            Some("/srv/castiron".into()),
+            Some("/xdg/data".into()),
+            None,
+            None,
+            Some("/home/user".into()),
+        );
+        assert_eq!(
+            resolved.database(),
+            PathBuf::from("/srv/castiron/database.sqlite")
+        );
+        assert_eq!(
+            resolved.config_file(),
+            PathBuf::from("/srv/castiron/castiron_config.json")
+        );
+        assert_eq!(resolved.shows_dir(), PathBuf::from("/srv/castiron/shows"));
+    }
+
+    #[test]
+    fn test_xdg_dirs_with_home_fallback() {
+        let resolved = Paths::resolve(
+            Some("".into()),
+            Some("/xdg/data".into()),
+            None,
+            None,
+            Some("/home/user".into()),
+        );
+        assert_eq!(
+            resolved.database(),
+            PathBuf::from("/xdg/data/castiron/database.sqlite")
+        );
+        assert_eq!(
+            resolved.config_file(),
+            PathBuf::from("/home/user/.config/castiron/castiron_config.json")
+        );
+        assert_eq!(
+            resolved.thumbnails_dir(),
+            PathBuf::from("/home/user/.cache/castiron/thumbnails")
+        );
+    }
+
+    #[test]
+    fn test_portable_without_home() {
+        let resolved = Paths::resolve(None, None, None, None, None);
+        assert_eq!(resolved.database(), portable_root().join("database.sqlite"));
+    }
+}
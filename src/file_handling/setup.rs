@@ -1,63 +1,34 @@
-use sqlite::open;
-use std::{fs::create_dir, path::Path};
-// use tokio::fs::create_dir;
+use std::{fs::create_dir_all, sync::Arc};
 
 use crate::{
     file_handling::{
-        config::load_or_create_config, episodes::get_episode_list_database,
-        feeds::get_feed_list_database, queue::get_queue_database,
+        config::load_or_create_config, database::Store, library::Library, paths::paths,
     },
+    networking::client::HttpClient,
     types::{config::CastironConfig, episodes::Episode, errors::CustomError, feeds::FeedMeta},
 };
 
-fn create_shows_directory_if_not_existing() -> Result<(), CustomError> {
-    match Path::new("./shows").exists() {
-        true => Ok(()),
-        false => {
-            create_dir(Path::new("./shows"))?;
-            Ok(())
-        }
+fn create_directories_if_not_existing() -> Result<(), CustomError> {
+    for dir in [
+        paths().config_dir(),
+        paths().shows_dir(),
+        paths().episodes_dir(),
+        paths().thumbnails_dir(),
+    ] {
+        create_dir_all(dir)?;
     }
-}
-
-fn create_episodes_directory_if_not_existing() -> Result<(), CustomError> {
-    match Path::new("./episodes").exists() {
-        true => Ok(()),
-        false => {
-            create_dir(Path::new("./episodes"))?;
-            Ok(())
-        }
-    }
-}
-
-fn create_thumbnails_directory_if_not_existing() -> Result<(), CustomError> {
-    match Path::new("./thumbnails").exists() {
-        true => Ok(()),
-        false => {
-            create_dir(Path::new("./thumbnails"))?;
-            Ok(())
-        }
-    }
-}
-
-fn create_database_if_not_existing() -> Result<(), CustomError> {
-    let connection = open(Path::new("./database.sqlite"))?;
-    let query = format!("
-        CREATE TABLE IF NOT EXISTS feeds(id INTEGER PRIMARY KEY, url TEXT NOT NULL, xml_file_path TEXT, feed_title TEXT, image_file_path TEXT);
-        CREATE TABLE IF NOT EXISTS episodes(id INTEGER PRIMARY KEY, guid TEXT, title TEXT, date DATE, played BOOLEAN, played_seconds INTEGER, file_name TEXT, url TEXT, feed_id INTEGER, downloaded BOOLEAN);
-        CREATE UNIQUE INDEX IF NOT EXISTS guid_feed_id ON episodes (guid,feed_id);
-        CREATE TABLE IF NOT EXISTS queue(id INTEGER PRIMARY KEY, episodes JSON);
-        ");
-    connection.execute(query)?;
     Ok(())
 }
 
-fn load_existing_user_state() -> Result<InitData, CustomError> {
+fn load_existing_user_state(library: Arc<dyn Library>) -> Result<InitData, CustomError> {
     let config = load_or_create_config()?;
-    let feeds = get_feed_list_database()?;
-    let episodes = get_episode_list_database()?;
-    let queue = get_queue_database()?;
+    let http = HttpClient::new(&config)?;
+    let feeds = library.get_feeds()?;
+    let episodes = library.get_episodes()?;
+    let queue = library.get_queue()?;
     Ok(InitData {
+        library,
+        http,
         config,
         feeds,
         episodes,
@@ -66,16 +37,16 @@ fn load_existing_user_state() -> Result<InitData, CustomError> {
 }
 #[derive(Debug, Clone)]
 pub struct InitData {
+    pub library: Arc<dyn Library>,
+    pub http: HttpClient,
     pub config: CastironConfig,
     pub feeds: Vec<FeedMeta>,
     pub episodes: Vec<Episode>,
     pub queue: Vec<Episode>,
 }
 pub async fn init_fs_and_db() -> Result<InitData, CustomError> {
-    create_shows_directory_if_not_existing()?;
-    create_episodes_directory_if_not_existing()?;
-    create_thumbnails_directory_if_not_existing()?;
-    create_database_if_not_existing()?;
-    let init_data = load_existing_user_state()?;
+    create_directories_if_not_existing()?;
+    let store = Store::open(paths().database())?;
+    let init_data = load_existing_user_state(Arc::new(store))?;
     Ok(init_data)
 }
@@ -1,5 +1,6 @@
 mod file_handling;
 mod networking;
+mod parsing;
 mod types;
 mod ui;
 
@@ -18,7 +19,7 @@ fn main() -> iced::Result {
                 Castiron::default(),
                 Task::perform(init_fs_and_db(), |res| match res {
                     Ok(init_data) => Message::InitComplete(init_data),
-                    Err(_) => Message::InitFailed,
+                    Err(e) => Message::InitFailed(e.to_string()),
                 }),
             )
         })
@@ -0,0 +1,127 @@
+use bytes::Bytes;
+use reqwest::{redirect::Policy, Client, Proxy, RequestBuilder, Response};
+use std::time::Duration;
+use tokio::time::timeout;
+use url::Url;
+
+use crate::types::{config::CastironConfig, errors::CustomError, feeds::FeedCredentials};
+
+/// The HTTP client all of Castiron's requests go through, set up from the network settings in
+/// `CastironConfig`. Cloning it is cheap and shares the underlying connection pool.
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    client: Client,
+    read_timeout: Duration,
+}
+
+impl HttpClient {
+    /// Fails if the settings cannot be applied, such as a malformed proxy URL, rather than
+    /// quietly sending requests without the proxy.
+    pub fn new(config: &CastironConfig) -> Result<Self, CustomError> {
+        let redirects = match config.max_redirects {
+            0 => Policy::none(),
+            limit => Policy::limited(limit),
+        };
+        let mut builder = Client::builder()
+            .user_agent(config.user_agent.as_str())
+            .connect_timeout(Duration::from_secs(config.connect_timeout_seconds))
+            .redirect(redirects);
+        if let Some(proxy) = config.proxy.as_deref() {
+            builder = builder.proxy(Proxy::all(proxy)?);
+        }
+        Ok(Self {
+            client: builder.build()?,
+            read_timeout: Duration::from_secs(config.read_timeout_seconds),
+        })
+    }
+
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.client.get(url)
+    }
+
+    /// A request for something behind a private feed's credentials.
+    pub fn get_authorized(
+        &self,
+        url: &str,
+        credentials: Option<&FeedCredentials>,
+    ) -> Result<RequestBuilder, CustomError> {
+        let Some(credentials) = credentials else {
+            return Ok(self.get(url));
+        };
+        let mut url = Url::parse(url)?;
+        if let (Some(parameter), Some(token)) = (&credentials.token_parameter, &credentials.token) {
+            url.query_pairs_mut().append_pair(parameter, token);
+        }
+        let mut request = self.client.get(url);
+        if let Some(username) = &credentials.username {
+            request = request.basic_auth(username, credentials.password.as_ref());
+        }
+        Ok(request)
+    }
+
+    /// The next chunk of a response body, failing if the server sends nothing for longer
+    /// than the read timeout.
+    pub async fn chunk(&self, response: &mut Response) -> Result<Option<Bytes>, CustomError> {
+        Ok(timeout(self.read_timeout, response.chunk()).await??)
+    }
+
+    /// A whole response body, read with the same timeout between chunks as `chunk`.
+    pub async fn bytes(&self, mut response: Response) -> Result<Vec<u8>, CustomError> {
+        let mut body = Vec::new();
+        while let Some(chunk) = self.chunk(&mut response).await? {
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_handling::config::default_config;
+    use reqwest::header::AUTHORIZATION;
+
+    #[test]
+    fn test_rejects_a_malformed_proxy() {
+        let config = CastironConfig {
+            proxy: Some(String::from("not a proxy")),
+            ..default_config()
+        };
+        assert!(HttpClient::new(&config).is_err());
+        let config = CastironConfig {
+            proxy: Some(String::from("socks5h://127.0.0.1:9050")),
+            ..default_config()
+        };
+        assert!(HttpClient::new(&config).is_ok());
+    }
+
+    #[test]
+    fn test_get_authorized_applies_credentials() {
+        let http = HttpClient::new(&default_config()).unwrap();
+        let credentials = FeedCredentials {
+            username: Some(String::from("listener")),
+            password: Some(String::from("hunter2")),
+            token_parameter: Some(String::from("auth")),
+            token: Some(String::from("s3cret")),
+        };
+        let request = http
+            .get_authorized("https://example.com/feed.xml?page=2", Some(&credentials))
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(
+            request.url().as_str(),
+            "https://example.com/feed.xml?page=2&auth=s3cret"
+        );
+        assert!(request.headers()[AUTHORIZATION]
+            .to_str()
+            .unwrap()
+            .starts_with("Basic "));
+        let request = http
+            .get_authorized("https://example.com/feed.xml", None)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(!request.headers().contains_key(AUTHORIZATION));
+    }
+}
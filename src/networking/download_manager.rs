@@ -0,0 +1,320 @@
+use crate::{
+    networking::downloads::{DownloadFailure, DownloadProgress},
+    types::config::default_download_concurrency,
+};
+use std::time::Duration;
+
+/// Attempts made at a download before it is left as failed.
+pub const MAX_ATTEMPTS: u32 = 4;
+const BASE_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// How long to wait before making `attempt`, counting from zero. Doubles with each retry.
+pub fn retry_delay(attempt: u32) -> Duration {
+    match attempt {
+        0 => Duration::ZERO,
+        _ => BASE_RETRY_DELAY * 2u32.pow(attempt - 1),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobState {
+    Queued,
+    Running {
+        attempt: u32,
+        progress: DownloadProgress,
+    },
+    Paused,
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct DownloadJob {
+    pub episode_id: i32,
+    pub title: String,
+    pub file_name: String,
+    pub state: JobState,
+}
+
+/// Keeps track of requested downloads and decides which of them should be running. The
+/// downloads themselves are driven by the UI, which runs one subscription per running job.
+pub struct DownloadManager {
+    jobs: Vec<DownloadJob>,
+    max_concurrent: usize,
+}
+
+impl Default for DownloadManager {
+    fn default() -> Self {
+        Self::new(default_download_concurrency())
+    }
+}
+
+impl DownloadManager {
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            jobs: Vec::new(),
+            max_concurrent,
+        }
+    }
+
+    /// Changes how many downloads run at once. Lowering it lets running downloads finish
+    /// rather than stopping them.
+    pub fn set_max_concurrent(&mut self, max_concurrent: usize) {
+        self.max_concurrent = max_concurrent.max(1);
+        self.schedule();
+    }
+
+    pub fn jobs(&self) -> &[DownloadJob] {
+        &self.jobs
+    }
+
+    /// Adds a download to the queue. Asking again for a paused or failed download requeues it.
+    pub fn enqueue(&mut self, episode_id: i32, title: String, file_name: String) {
+        match self.job_mut(episode_id) {
+            Some(job) => {
+                if matches!(job.state, JobState::Paused | JobState::Failed(_)) {
+                    job.state = JobState::Queued;
+                }
+            }
+            None => self.jobs.push(DownloadJob {
+                episode_id,
+                title,
+                file_name,
+                state: JobState::Queued,
+            }),
+        }
+        self.schedule();
+    }
+
+    /// The running downloads as `(episode_id, attempt)`.
+    pub fn running(&self) -> Vec<(i32, u32)> {
+        self.jobs
+            .iter()
+            .filter_map(|job| match job.state {
+                JobState::Running { attempt, .. } => Some((job.episode_id, attempt)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Progress to show for an episode that is waiting on or in the middle of a download.
+    pub fn progress(&self, episode_id: i32) -> Option<DownloadProgress> {
+        self.jobs
+            .iter()
+            .find(|job| job.episode_id == episode_id)
+            .and_then(|job| match job.state {
+                JobState::Queued => Some(DownloadProgress::default()),
+                JobState::Running { progress, .. } => Some(progress),
+                _ => None,
+            })
+    }
+
+    pub fn update_progress(&mut self, episode_id: i32, update: DownloadProgress) {
+        if let Some(JobState::Running { progress, .. }) =
+            self.job_mut(episode_id).map(|job| &mut job.state)
+        {
+            *progress = update;
+        }
+    }
+
+    /// Records the outcome of a download. Transient failures are retried until `MAX_ATTEMPTS`
+    /// is reached. A download that finished just as it was paused or cancelled still counts,
+    /// since the episode is on disk either way. Returns whether the download completed.
+    pub fn finish(&mut self, episode_id: i32, result: Result<(), DownloadFailure>) -> bool {
+        let Some(job) = self.job_mut(episode_id) else {
+            return result.is_ok();
+        };
+        let completed = match (result, &job.state) {
+            (Ok(_), _) => {
+                self.jobs.retain(|job| job.episode_id != episode_id);
+                true
+            }
+            (Err(failure), JobState::Running { attempt, .. }) => {
+                let attempt = *attempt;
+                job.state = match failure.transient && attempt + 1 < MAX_ATTEMPTS {
+                    true => JobState::Running {
+                        attempt: attempt + 1,
+                        progress: DownloadProgress::default(),
+                    },
+                    false => JobState::Failed(failure.message),
+                };
+                false
+            }
+            // Paused since this attempt started, so its failure no longer matters.
+            (Err(_), _) => return false,
+        };
+        self.schedule();
+        completed
+    }
+
+    pub fn pause(&mut self, episode_id: i32) {
+        if let Some(job) = self.job_mut(episode_id) {
+            if matches!(job.state, JobState::Queued | JobState::Running { .. }) {
+                job.state = JobState::Paused;
+            }
+        }
+        self.schedule();
+    }
+
+    /// Requeues a paused or failed download.
+    pub fn resume(&mut self, episode_id: i32) {
+        if let Some(job) = self.job_mut(episode_id) {
+            if matches!(job.state, JobState::Paused | JobState::Failed(_)) {
+                job.state = JobState::Queued;
+            }
+        }
+        self.schedule();
+    }
+
+    /// Drops a download entirely, returning it so the caller can clean up after it.
+    pub fn cancel(&mut self, episode_id: i32) -> Option<DownloadJob> {
+        let index = self
+            .jobs
+            .iter()
+            .position(|job| job.episode_id == episode_id)?;
+        let job = self.jobs.remove(index);
+        self.schedule();
+        Some(job)
+    }
+
+    pub fn clear_failed(&mut self) {
+        self.jobs
+            .retain(|job| !matches!(job.state, JobState::Failed(_)));
+    }
+
+    fn job_mut(&mut self, episode_id: i32) -> Option<&mut DownloadJob> {
+        self.jobs
+            .iter_mut()
+            .find(|job| job.episode_id == episode_id)
+    }
+
+    /// Starts queued downloads, oldest first, until `max_concurrent` are running.
+    fn schedule(&mut self) {
+        let mut running = self.running().len();
+        for job in self.jobs.iter_mut() {
+            if running >= self.max_concurrent {
+                break;
+            }
+            if job.state == JobState::Queued {
+                job.state = JobState::Running {
+                    attempt: 0,
+                    progress: DownloadProgress::default(),
+                };
+                running += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(transient: bool) -> Result<(), DownloadFailure> {
+        Err(DownloadFailure {
+            message: String::from("connection reset"),
+            transient,
+        })
+    }
+
+    fn manager_with(count: i32, max_concurrent: usize) -> DownloadManager {
+        let mut manager = DownloadManager::new(max_concurrent);
+        for id in 1..=count {
+            manager.enqueue(id, format!("Episode {id}"), format!("{id}.mp3"));
+        }
+        manager
+    }
+
+    #[test]
+    fn test_runs_up_to_the_limit() {
+        let mut manager = manager_with(3, 2);
+        assert_eq!(manager.running(), vec![(1, 0), (2, 0)]);
+        assert_eq!(manager.progress(3), Some(DownloadProgress::default()));
+        assert!(manager.finish(1, Ok(())));
+        assert_eq!(manager.running(), vec![(2, 0), (3, 0)]);
+        assert_eq!(manager.jobs().len(), 2);
+    }
+
+    #[test]
+    fn test_retries_transient_failures_then_gives_up() {
+        let mut manager = manager_with(1, 1);
+        for attempt in 1..MAX_ATTEMPTS {
+            assert!(!manager.finish(1, failure(true)));
+            assert_eq!(manager.running(), vec![(1, attempt)]);
+        }
+        manager.finish(1, failure(true));
+        assert!(manager.running().is_empty());
+        assert_eq!(
+            manager.jobs()[0].state,
+            JobState::Failed(String::from("connection reset"))
+        );
+        assert_eq!(manager.progress(1), None);
+
+        manager.resume(1);
+        assert_eq!(manager.running(), vec![(1, 0)]);
+        manager.finish(1, failure(false));
+        assert!(matches!(manager.jobs()[0].state, JobState::Failed(_)));
+        manager.clear_failed();
+        assert!(manager.jobs().is_empty());
+    }
+
+    #[test]
+    fn test_pause_and_cancel_free_up_slots() {
+        let mut manager = manager_with(3, 1);
+        manager.pause(1);
+        assert_eq!(manager.running(), vec![(2, 0)]);
+        // A failure from the paused attempt arriving late is ignored.
+        assert!(!manager.finish(1, failure(true)));
+        assert_eq!(manager.jobs()[0].state, JobState::Paused);
+
+        assert_eq!(
+            manager.cancel(2).map(|job| job.file_name),
+            Some("2.mp3".into())
+        );
+        assert_eq!(manager.running(), vec![(3, 0)]);
+        manager.resume(1);
+        assert_eq!(manager.jobs()[0].state, JobState::Queued);
+        manager.finish(3, Ok(()));
+        assert_eq!(manager.running(), vec![(1, 0)]);
+    }
+
+    #[test]
+    fn test_success_after_pause_completes_the_download() {
+        let mut manager = manager_with(2, 1);
+        manager.pause(1);
+        assert_eq!(manager.running(), vec![(2, 0)]);
+        assert!(manager.finish(1, Ok(())));
+        assert_eq!(manager.jobs().len(), 1);
+        assert_eq!(manager.jobs()[0].episode_id, 2);
+        manager.resume(1);
+        assert_eq!(manager.running(), vec![(2, 0)]);
+    }
+
+    #[test]
+    fn test_success_after_cancel_completes_the_download() {
+        let mut manager = manager_with(2, 1);
+        manager.cancel(1);
+        assert_eq!(manager.running(), vec![(2, 0)]);
+        assert!(manager.finish(1, Ok(())));
+        assert!(!manager.finish(1, failure(true)));
+        assert_eq!(manager.running(), vec![(2, 0)]);
+    }
+
+    #[test]
+    fn test_changing_the_limit() {
+        let mut manager = manager_with(3, 1);
+        manager.set_max_concurrent(2);
+        assert_eq!(manager.running(), vec![(1, 0), (2, 0)]);
+        manager.set_max_concurrent(1);
+        assert_eq!(manager.running(), vec![(1, 0), (2, 0)]);
+        manager.finish(1, Ok(()));
+        manager.finish(2, Ok(()));
+        assert_eq!(manager.running(), vec![(3, 0)]);
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        assert_eq!(retry_delay(0), Duration::ZERO);
+        assert_eq!(retry_delay(1), Duration::from_secs(2));
+        assert_eq!(retry_delay(3), Duration::from_secs(8));
+    }
+}
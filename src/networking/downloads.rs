@@ -1,44 +1,384 @@
-use reqwest::get;
-use std::{
-    fs::File,
-    io::{copy, Cursor},
-    path::Path,
+use futures::{stream, StreamExt};
+use reqwest::{
+    header::{CONTENT_RANGE, CONTENT_TYPE, RANGE},
+    Response, StatusCode,
 };
+use std::path::{Path, PathBuf};
+use tokio::{fs, io::AsyncWriteExt};
 
 use crate::{
-    file_handling::episodes::{get_episode_by_id, update_episode_download_status},
-    types::errors::CustomError,
+    file_handling::{
+        library::Library,
+        media_types::{extension_for_mime_type, with_extension},
+        paths::paths,
+    },
+    networking::client::HttpClient,
+    parsing::stable_hash,
+    types::{
+        errors::CustomError,
+        feeds::{FeedCredentials, FeedMeta},
+    },
 };
+use url::Url;
 
-async fn download_episode(url: &str, file_name: &str) -> Result<String, CustomError> {
-    let mut directory = File::create(Path::new(format!("./episodes/{file_name}").as_str()))?;
-    let response = get(url).await?;
-    let mut content = Cursor::new(response.bytes().await?);
-    copy(&mut content, &mut directory)?;
-    Ok(String::from("Download successful"))
+/// How many episode images are fetched at once while a feed is imported.
+const EPISODE_THUMBNAIL_CONCURRENCY: usize = 4;
+
+/// Progress is reported each time at least this many more bytes have arrived.
+const PROGRESS_STEP_BYTES: u64 = 256 * 1024;
+
+/// How far along a download is. `total` is the response's Content-Length, when it sent one.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DownloadProgress {
+    pub downloaded: u64,
+    pub total: Option<u64>,
+}
+
+impl DownloadProgress {
+    /// The share of the download completed, between 0 and 1, if the total size is known.
+    pub fn fraction(&self) -> Option<f32> {
+        match self.total {
+            Some(total) if total > 0 => Some((self.downloaded as f32 / total as f32).min(1.0)),
+            _ => None,
+        }
+    }
+}
+
+/// Why a download stopped, and whether trying again later might help.
+#[derive(Debug, Clone)]
+pub struct DownloadFailure {
+    pub message: String,
+    pub transient: bool,
+}
+
+impl From<CustomError> for DownloadFailure {
+    fn from(err: CustomError) -> Self {
+        let transient = match &err {
+            CustomError::ReqwestError(e) => match e.status() {
+                Some(status) => {
+                    status.is_server_error()
+                        || status == StatusCode::TOO_MANY_REQUESTS
+                        || status == StatusCode::REQUEST_TIMEOUT
+                }
+                None => e.is_timeout() || e.is_connect() || e.is_body() || e.is_request(),
+            },
+            CustomError::IncompleteDownload { .. } | CustomError::Timeout(_) => true,
+            _ => false,
+        };
+        DownloadFailure {
+            message: err.to_string(),
+            transient,
+        }
+    }
+}
+
+fn partial_file(file_name: &str) -> PathBuf {
+    paths().episode_file(&format!("{file_name}.part"))
 }
 
-pub async fn download_episode_by_guid(id: i32) -> Result<String, CustomError> {
-    let episode = get_episode_by_id(id)?;
-    download_episode(episode.url.as_str(), episode.file_name.as_str()).await?;
-    update_episode_download_status(id, true)?;
+/// The first byte and, if given, the full length from a `Content-Range: bytes 100-199/200`
+/// header.
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _) = range.split_once('-')?;
+    Some((start.trim().parse().ok()?, total.trim().parse().ok()))
+}
+
+/// Requests the episode, asking only for the bytes after `offset` when there is a partial file
+/// to continue. Falls back to a full download if the server cannot resume from there.
+async fn request_episode(
+    http: &HttpClient,
+    url: &str,
+    credentials: Option<&FeedCredentials>,
+    partial: &Path,
+    offset: u64,
+) -> Result<Response, CustomError> {
+    if offset == 0 {
+        return Ok(http.get_authorized(url, credentials)?.send().await?);
+    }
+    let response = http
+        .get_authorized(url, credentials)?
+        .header(RANGE, format!("bytes={offset}-"))
+        .send()
+        .await?;
+    let resumes_at = response
+        .headers()
+        .get(CONTENT_RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_content_range)
+        .map(|(start, _)| start);
+    match response.status() {
+        StatusCode::PARTIAL_CONTENT if resumes_at == Some(offset) => Ok(response),
+        StatusCode::PARTIAL_CONTENT | StatusCode::RANGE_NOT_SATISFIABLE => {
+            fs::remove_file(partial).await?;
+            Ok(http.get_authorized(url, credentials)?.send().await?)
+        }
+        _ => Ok(response),
+    }
+}
+
+/// A private feed's credentials only go along with enclosures served from the feed's own
+/// origin, so they are never handed to a third-party host.
+fn enclosure_credentials<'a>(feed: &'a FeedMeta, url: &str) -> Option<&'a FeedCredentials> {
+    let origin = |url: &str| Url::parse(url).ok().map(|parsed| parsed.origin());
+    match (origin(url), origin(&feed.feed_url)) {
+        (Some(enclosure), Some(own)) if enclosure == own => feed.credentials.as_ref(),
+        _ => None,
+    }
+}
+
+/// Removes whatever a cancelled download had written so far.
+pub fn discard_partial_download(file_name: &str) {
+    std::fs::remove_file(partial_file(file_name)).unwrap_or(())
+}
+
+/// The name to save an episode under once the server has said what it is sending. Feeds
+/// often declare the wrong type, so a recognised `Content-Type` wins over the extension the
+/// name was given from the feed; generic or unknown types leave the name alone.
+fn file_name_for_response(file_name: &str, response: &Response) -> String {
+    response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(extension_for_mime_type)
+        .map(|extension| with_extension(file_name, extension))
+        .unwrap_or_else(|| file_name.to_string())
+}
+
+/// Streams the episode into a `.part` file next to its destination and renames it into place
+/// once complete, so a failed download never leaves a truncated episode behind. An existing
+/// `.part` file from an interrupted attempt is continued with a `Range` request. Returns the
+/// name the episode was saved under, which differs from `file_name` when the server's
+/// `Content-Type` calls for another extension; the `.part` file keeps the original name.
+async fn download_episode<F: FnMut(DownloadProgress)>(
+    http: &HttpClient,
+    url: &str,
+    credentials: Option<&FeedCredentials>,
+    file_name: &str,
+    mut on_progress: F,
+) -> Result<String, CustomError> {
+    let partial = partial_file(file_name);
+    if let Some(dir) = partial.parent() {
+        fs::create_dir_all(dir).await?;
+    }
+    let offset = fs::metadata(&partial)
+        .await
+        .map(|meta| meta.len())
+        .unwrap_or(0);
+    let mut response = request_episode(http, url, credentials, &partial, offset)
+        .await?
+        .error_for_status()?;
+    let saved_name = file_name_for_response(file_name, &response);
+    let destination = paths().episode_file(&saved_name);
+    let resuming = response.status() == StatusCode::PARTIAL_CONTENT;
+    let start = if resuming { offset } else { 0 };
+    let total = match resuming {
+        true => response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_content_range)
+            .and_then(|(_, total)| total),
+        false => None,
+    }
+    .or(response.content_length().map(|length| start + length));
+    let mut progress = DownloadProgress {
+        downloaded: start,
+        total,
+    };
+    on_progress(progress);
+    let mut file = match resuming {
+        true => fs::OpenOptions::new().append(true).open(&partial).await?,
+        false => fs::File::create(&partial).await?,
+    };
+    let mut reported: u64 = start;
+    let streamed: Result<(), CustomError> = async {
+        while let Some(chunk) = http.chunk(&mut response).await? {
+            file.write_all(&chunk).await?;
+            progress.downloaded += chunk.len() as u64;
+            if progress.downloaded - reported >= PROGRESS_STEP_BYTES {
+                reported = progress.downloaded;
+                on_progress(progress);
+            }
+        }
+        file.flush().await?;
+        Ok(())
+    }
+    .await;
+    drop(file);
+    // Whatever arrived stays in the `.part` file for the next attempt to pick up from.
+    streamed?;
+    if let Some(expected) = progress.total {
+        if progress.downloaded != expected {
+            return Err(CustomError::IncompleteDownload {
+                expected,
+                received: progress.downloaded,
+            });
+        }
+    }
+    fs::rename(&partial, &destination).await?;
+    on_progress(progress);
+    Ok(saved_name)
+}
+
+pub async fn download_episode_by_guid<F: FnMut(DownloadProgress)>(
+    library: &dyn Library,
+    http: &HttpClient,
+    id: i32,
+    on_progress: F,
+) -> Result<String, CustomError> {
+    let episode = library.get_episode_by_id(id)?;
+    let feed = library.get_feed_by_id(episode.feed_id)?;
+    let saved_name = download_episode(
+        http,
+        episode.url.as_str(),
+        enclosure_credentials(&feed, &episode.url),
+        episode.file_name.as_str(),
+        on_progress,
+    )
+    .await?;
+    if saved_name != episode.file_name {
+        library.update_episode_file_name(id, saved_name)?;
+    }
+    library.update_episode_download_status(id, true)?;
     Ok(String::from("Download successful."))
 }
 
 pub async fn download_feed_thumbnail(
+    http: &HttpClient,
     url: &str,
     file_extension: &str,
     feed_id: i32,
 ) -> Result<(), CustomError> {
-    let mut file_path = File::create(Path::new(
-        format!("./thumbnails/{feed_id}.{file_extension}").as_str(),
-    ))?;
-    let response = get(url).await?;
-    let mut content = Cursor::new(response.bytes().await?);
-    copy(&mut content, &mut file_path)?;
+    let response = http.get(url).send().await?.error_for_status()?;
+    let content = http.bytes(response).await?;
+    fs::write(paths().thumbnail_file(feed_id, file_extension), content).await?;
     Ok(())
 }
 
 pub fn check_thumbnail_exists(feed_id: i32, file_extension: &str) -> bool {
-    Path::new(format!("./thumbnails/{feed_id}.{file_extension}").as_str()).exists()
+    paths().thumbnail_file(feed_id, file_extension).exists()
+}
+
+/// Where an episode's own artwork is cached. Files are named after the image's URL, so
+/// episodes that share artwork share a file.
+pub fn episode_thumbnail_file(feed_id: i32, url: &str) -> PathBuf {
+    let extension = Url::parse(url)
+        .ok()
+        .and_then(|url| {
+            let (_, extension) = url.path_segments()?.next_back()?.rsplit_once('.')?;
+            Some(extension.to_ascii_lowercase())
+        })
+        .filter(|extension| {
+            (1..=4).contains(&extension.len())
+                && extension.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+        .unwrap_or_else(|| String::from("jpg"));
+    paths()
+        .episode_thumbnails_dir(feed_id)
+        .join(format!("{:016x}.{extension}", stable_hash(url)))
+}
+
+/// Downloads whichever of a feed's episode artwork is not cached yet, a few at a time.
+/// Artwork is decoration, so images that fail to download are left for the next refresh.
+pub async fn download_episode_thumbnails(http: &HttpClient, feed_id: i32, mut urls: Vec<String>) {
+    urls.sort();
+    urls.dedup();
+    urls.retain(|url| !episode_thumbnail_file(feed_id, url).exists());
+    if urls.is_empty() {
+        return;
+    }
+    if fs::create_dir_all(paths().episode_thumbnails_dir(feed_id))
+        .await
+        .is_err()
+    {
+        return;
+    }
+    stream::iter(urls)
+        .for_each_concurrent(EPISODE_THUMBNAIL_CONCURRENCY, |url| async move {
+            let _ = download_episode_thumbnail(http, feed_id, &url).await;
+        })
+        .await;
+}
+
+async fn download_episode_thumbnail(
+    http: &HttpClient,
+    feed_id: i32,
+    url: &str,
+) -> Result<(), CustomError> {
+    let response = http.get(url).send().await?.error_for_status()?;
+    let content = http.bytes(response).await?;
+    fs::write(episode_thumbnail_file(feed_id, url), content).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::feeds::FeedDetails;
+
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(
+            parse_content_range("bytes 100-199/200"),
+            Some((100, Some(200)))
+        );
+        assert_eq!(parse_content_range("bytes 100-199/*"), Some((100, None)));
+        assert_eq!(parse_content_range("bytes */200"), None);
+        assert_eq!(parse_content_range("items 0-1/2"), None);
+    }
+
+    #[test]
+    fn test_enclosure_credentials_stay_on_the_feed_origin() {
+        let feed = FeedMeta {
+            id: 1,
+            feed_url: String::from("https://private.example.com/feed.xml"),
+            xml_file_path: None,
+            feed_title: None,
+            image_file_path: None,
+            playback_speed: None,
+            etag: None,
+            last_modified: None,
+            credentials: Some(FeedCredentials {
+                token_parameter: Some(String::from("auth")),
+                token: Some(String::from("s3cret")),
+                ..FeedCredentials::default()
+            }),
+            details: FeedDetails::default(),
+        };
+        assert!(
+            enclosure_credentials(&feed, "https://private.example.com/episodes/1.mp3").is_some()
+        );
+        assert!(enclosure_credentials(&feed, "https://cdn.example.net/1.mp3").is_none());
+        assert!(enclosure_credentials(&feed, "http://private.example.com/1.mp3").is_none());
+    }
+
+    #[test]
+    fn test_episode_thumbnail_file() {
+        let artwork = episode_thumbnail_file(3, "https://cdn.example.com/art/ep1.PNG?size=600");
+        assert_eq!(
+            artwork.parent(),
+            Some(paths().episode_thumbnails_dir(3).as_path())
+        );
+        assert_eq!(artwork.extension(), Some("png".as_ref()));
+        assert_eq!(
+            artwork,
+            episode_thumbnail_file(3, "https://cdn.example.com/art/ep1.PNG?size=600")
+        );
+        assert_ne!(
+            artwork,
+            episode_thumbnail_file(3, "https://cdn.example.com/art/ep2.PNG?size=600")
+        );
+        for odd in [
+            "https://cdn.example.com/art/",
+            "https://cdn.example.com/art.x/y",
+            "nonsense",
+        ] {
+            assert_eq!(
+                episode_thumbnail_file(3, odd).extension(),
+                Some("jpg".as_ref()),
+                "{odd}"
+            );
+        }
+    }
 }
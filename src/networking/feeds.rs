@@ -1,159 +1,277 @@
 use crate::{
     file_handling::{
-        episodes::{
-            add_episode_to_database, get_episode_list_database,
-            mark_episodes_deleted_if_file_nonexistent,
-        },
-        feeds::{
-            get_feed_list_database, load_feed_xml, update_feed_file_path, update_feed_title,
-            update_thumbnail_file_path,
+        episodes::{episode_file_name, mark_episodes_deleted_if_file_nonexistent},
+        feeds::load_feed_xml,
+        library::Library,
+        media_types::enclosure_extension,
+        paths::paths,
+    },
+    parsing::parse_feed,
+    types::{
+        config::{
+            default_feed_refresh_concurrency, default_feed_request_timeout_seconds, CastironConfig,
         },
+        episodes::Episode,
+        errors::CustomError,
+        feeds::{FeedDetails, FeedMeta},
     },
-    types::{episodes::Episode, errors::CustomError, feeds::FeedMeta},
 };
 
-use bytes::Bytes;
-use reqwest::{get, Error};
-use roxmltree::Document;
-use std::{
-    fs::OpenOptions,
-    io::{copy, Cursor, Seek},
+use futures::{stream, StreamExt};
+use reqwest::{
+    header::{HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+    Response, StatusCode,
 };
-use tokio::io::SeekFrom;
+use std::{fs::write, time::Duration};
+use time::format_description::well_known::Rfc3339;
 use url::{Position, Url};
 
-use super::downloads::{check_thumbnail_exists, download_feed_thumbnail};
-
-pub async fn sync_episode_list() -> Result<Option<Vec<Episode>>, CustomError> {
-    update_feeds().await?;
-    mark_episodes_deleted_if_file_nonexistent()?;
-    let feed_collection = get_feed_list_database()?;
-    let mut episodes: Vec<Episode> = Vec::new();
-    for feed in feed_collection {
-        let content = load_feed_xml(feed.xml_file_path.unwrap_or(String::new()))?;
-        let feed_contents = content.as_str();
-        let doc = Document::parse(feed_contents)?;
-        let channel_node = doc.descendants().find(|n| n.has_tag_name("channel"));
-        match channel_node {
-            Some(c_node) => {
-                if let Some(title_node) = c_node.descendants().find(|n| n.has_tag_name("title")) {
-                    update_feed_title(feed.id, title_node.text().unwrap().to_string())?;
-                }
-                if let Some(image_node) = c_node.descendants().find(|n| n.has_tag_name("image")) {
-                    if let Some(url_node) = image_node.descendants().find(|n| n.has_tag_name("url"))
-                    {
-                        if let Some(url) = url_node.text() {
-                            if let Ok(file_extension) = parse_file_extension_from_image_url(url) {
-                                if !check_thumbnail_exists(feed.id, file_extension.as_str()) {
-                                    download_feed_thumbnail(url, file_extension.as_str(), feed.id)
-                                        .await?;
-                                    update_thumbnail_file_path(
-                                        feed.id,
-                                        format!("./thumbnails/{}.{file_extension}", feed.id),
-                                    )?;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            None => (),
+use super::{
+    client::HttpClient,
+    downloads::{check_thumbnail_exists, download_episode_thumbnails, download_feed_thumbnail},
+};
+
+/// What syncing found for a single feed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeedStatus {
+    Unchanged,
+    Updated,
+    Failed(String),
+}
+
+/// Validators from a feed response, saved once the new XML has been read successfully.
+#[derive(Debug, Clone, Default)]
+pub struct CacheHeaders {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+pub enum FeedRefresh {
+    /// The server answered 304 Not Modified, so the cached XML is still current.
+    Unchanged,
+    Updated(CacheHeaders),
+}
+
+#[derive(Debug, Clone)]
+pub struct SyncResult {
+    pub episodes: Vec<Episode>,
+    pub feed_statuses: Vec<(i32, FeedStatus)>,
+}
+
+/// How a sync spreads its feed requests.
+#[derive(Debug, Clone, Copy)]
+pub struct RefreshLimits {
+    pub concurrency: usize,
+    pub request_timeout: Duration,
+}
+
+impl Default for RefreshLimits {
+    fn default() -> Self {
+        Self {
+            concurrency: default_feed_refresh_concurrency(),
+            request_timeout: Duration::from_secs(default_feed_request_timeout_seconds()),
         }
-        let episode_nodes = doc.descendants().filter(|n| n.has_tag_name("item"));
-        for e_node in episode_nodes {
-            let title_node = e_node.descendants().find(|n| n.has_tag_name("title"));
-            let episode_title = match title_node {
-                Some(t) => t.text().unwrap(),
-                None => "",
-            };
-            let date_node = e_node.descendants().find(|n| n.has_tag_name("pubDate"));
-            let episode_date = match date_node {
-                Some(d) => d.text().unwrap(),
-                None => "",
-            };
-            let guid_node = e_node.descendants().find(|n| n.has_tag_name("guid"));
-            match guid_node {
-                Some(g_node) => {
-                    let guid = g_node.text().unwrap();
-                    let enclosure_node = e_node.descendants().find(|n| n.has_tag_name("enclosure"));
-                    match enclosure_node {
-                        Some(e_node) => match e_node.attribute("url") {
-                            Some(url) => {
-                                let file_name = match e_node.attribute("type") {
-                                    Some("audio/aac") => format!("{guid}.aac"),
-                                    Some("audio/mpeg") => format!("{guid}.mp3"),
-                                    Some("audio/ogg") => format!("{guid}.oga"),
-                                    Some("audio/opus") => format!("{guid}.opus"),
-                                    Some("audio/wav") => format!("{guid}.wav"),
-                                    Some("audio/webm") => format!("{guid}.weba"),
-                                    Some(_) => format!("{guid}.mp3"),
-                                    None => "fail.mp3".to_string(),
-                                };
-                                episodes.push(Episode {
-                                    id: 0,
-                                    guid: guid.to_string(),
-                                    file_name,
-                                    title: episode_title.to_string(),
-                                    date: episode_date.to_string(),
-                                    played: false,
-                                    played_seconds: 0,
-                                    feed_id: feed.id,
-                                    url: url.to_string(),
-                                    downloaded: false,
-                                })
-                            }
-                            None => {
-                                eprintln!("No url found for {:?}.", g_node.text())
-                            }
-                        },
-                        None => (),
-                    }
-                }
-                None => (),
-            }
+    }
+}
+
+impl From<&CastironConfig> for RefreshLimits {
+    fn from(config: &CastironConfig) -> Self {
+        Self {
+            concurrency: config.feed_refresh_concurrency,
+            request_timeout: Duration::from_secs(config.feed_request_timeout_seconds),
         }
     }
-    for episode in episodes.into_iter() {
-        add_episode_to_database(episode)?;
+}
+
+pub async fn sync_episode_list(
+    library: &dyn Library,
+    http: &HttpClient,
+    limits: RefreshLimits,
+) -> Result<SyncResult, CustomError> {
+    let feed_statuses = update_feeds(library, http, limits).await?;
+    mark_episodes_deleted_if_file_nonexistent(library)?;
+    Ok(SyncResult {
+        episodes: library.get_episodes()?,
+        feed_statuses,
+    })
+}
+
+/// Refreshes and imports every feed, up to `limits.concurrency` at a time, returning what
+/// happened to each one.
+pub async fn update_feeds(
+    library: &dyn Library,
+    http: &HttpClient,
+    limits: RefreshLimits,
+) -> Result<Vec<(i32, FeedStatus)>, CustomError> {
+    let feeds = library.get_feeds()?;
+    Ok(stream::iter(feeds)
+        .map(|feed| async move {
+            let feed_id = feed.id;
+            (
+                feed_id,
+                sync_feed(library, http, feed, limits.request_timeout).await,
+            )
+        })
+        .buffer_unordered(limits.concurrency.max(1))
+        .collect()
+        .await)
+}
+
+async fn sync_feed(
+    library: &dyn Library,
+    http: &HttpClient,
+    feed: FeedMeta,
+    timeout: Duration,
+) -> FeedStatus {
+    let feed_id = feed.id;
+    match update_single_feed(library, http, feed, timeout).await {
+        Err(e) => FeedStatus::Failed(e.to_string()),
+        Ok(FeedRefresh::Unchanged) => FeedStatus::Unchanged,
+        Ok(FeedRefresh::Updated(headers)) => {
+            match import_feed_episodes(library, http, feed_id)
+                .await
+                .and_then(|_| {
+                    library.update_feed_cache_headers(feed_id, headers.etag, headers.last_modified)
+                }) {
+                Ok(_) => FeedStatus::Updated,
+                Err(e) => FeedStatus::Failed(e.to_string()),
+            }
+        }
     }
-    let result = get_episode_list_database()?;
-    Ok(Some(result))
 }
 
-pub async fn update_feeds() -> Result<(), CustomError> {
-    let feeds = get_feed_list_database()?;
-    for feed in feeds {
-        match update_single_feed(feed).await {
-            Ok(_) => (),
-            Err(e) => eprintln!("Error occurred while updating feed {:?}", e),
+/// Reads a feed's cached document, updating its title and thumbnail and adding any new
+/// episodes along with their own artwork.
+async fn import_feed_episodes(
+    library: &dyn Library,
+    http: &HttpClient,
+    feed_id: i32,
+) -> Result<(), CustomError> {
+    let feed = library.get_feed_by_id(feed_id)?;
+    let content = load_feed_xml(&paths().show_xml_file(feed.id))?;
+    let parsed = parse_feed(&content)?;
+    if let Some(title) = parsed.title {
+        library.update_feed_title(feed.id, title)?;
+    }
+    if let Some(url) = &parsed.image_url {
+        if let Ok(file_extension) = parse_file_extension_from_image_url(url) {
+            if !check_thumbnail_exists(feed.id, file_extension.as_str()) {
+                download_feed_thumbnail(http, url, file_extension.as_str(), feed.id).await?;
+            }
+            // Also replaces paths stored by earlier versions.
+            let file_name = format!("{}.{file_extension}", feed.id);
+            if feed.image_file_path.as_deref() != Some(file_name.as_str()) {
+                library.update_thumbnail_file_path(feed.id, file_name)?;
+            }
         }
     }
+    library.update_feed_details(
+        feed.id,
+        FeedDetails {
+            author: parsed.author,
+            description: parsed.description,
+            website: parsed.website,
+            language: parsed.language,
+            categories: parsed.categories,
+            explicit: parsed.explicit,
+            show_type: parsed.show_type,
+            copyright: parsed.copyright,
+        },
+    )?;
+    let episode_images = parsed
+        .episodes
+        .iter()
+        .filter_map(|episode| episode.image_url.to_owned())
+        .filter(|url| Some(url) != parsed.image_url.as_ref())
+        .collect();
+    download_episode_thumbnails(http, feed.id, episode_images).await;
+    for episode in parsed.episodes.into_iter() {
+        library.add_episode(Episode {
+            id: 0,
+            file_name: episode_file_name(
+                feed.id,
+                &episode.guid,
+                enclosure_extension(episode.mime_type.as_deref(), &episode.url),
+            ),
+            guid: episode.guid,
+            title: episode.title.unwrap_or_default(),
+            date: episode
+                .date
+                .and_then(|date| date.format(&Rfc3339).ok())
+                .unwrap_or_default(),
+            played: false,
+            played_seconds: 0,
+            feed_id: feed.id,
+            url: episode.url,
+            downloaded: false,
+            description: episode.description,
+            duration_seconds: episode.duration_seconds,
+            size_bytes: episode.size_bytes,
+            season: episode.season,
+            episode_number: episode.episode_number,
+            episode_type: episode.episode_type,
+            link: episode.link,
+            image_url: episode.image_url,
+        })?;
+    }
     Ok(())
 }
 
-pub async fn update_single_feed(feed: FeedMeta) -> Result<(), CustomError> {
-    let mut should_set_xml_path_equal_id = false;
-    let mut updated_feed = get_request(&feed.feed_url).await?;
-    let mut xml_file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(feed.xml_file_path.unwrap_or_else(|| {
-            should_set_xml_path_equal_id = true;
-            format!("./shows/{a}.xml", a = feed.id)
-        }))?;
-    xml_file.seek(SeekFrom::Start(0))?;
-    copy(&mut updated_feed, &mut xml_file)?;
-    if should_set_xml_path_equal_id {
-        update_feed_file_path(feed.id, format!("./shows/{a}.xml", a = feed.id))?;
+/// Downloads a feed's XML, unless the server reports that it has not changed since the last
+/// refresh.
+pub async fn update_single_feed(
+    library: &dyn Library,
+    http: &HttpClient,
+    feed: FeedMeta,
+    timeout: Duration,
+) -> Result<FeedRefresh, CustomError> {
+    let xml_file_path = paths().show_xml_file(feed.id);
+    // A 304 is only useful with a cached copy to fall back on.
+    let cached = xml_file_path.exists();
+    let response = get_request(http, &feed, cached, timeout).await?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(FeedRefresh::Unchanged);
     }
-    Ok(())
+    let headers = CacheHeaders {
+        etag: header_value(&response, ETAG),
+        last_modified: header_value(&response, LAST_MODIFIED),
+    };
+    write(&xml_file_path, http.bytes(response).await?)?;
+    let file_name = format!("{}.xml", feed.id);
+    if feed.xml_file_path.as_deref() != Some(file_name.as_str()) {
+        library.update_feed_file_path(feed.id, file_name)?;
+    }
+    Ok(FeedRefresh::Updated(headers))
+}
+
+/// Requests a feed, making it conditional on the validators from the last refresh when
+/// `conditional` is set.
+pub async fn get_request(
+    http: &HttpClient,
+    feed: &FeedMeta,
+    conditional: bool,
+    timeout: Duration,
+) -> Result<Response, CustomError> {
+    let mut request = http
+        .get_authorized(&feed.feed_url, feed.credentials.as_ref())?
+        .timeout(timeout);
+    if conditional {
+        if let Some(etag) = &feed.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &feed.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+    Ok(request.send().await?.error_for_status()?)
 }
 
-pub async fn get_request(url: &String) -> Result<Cursor<Bytes>, Error> {
-    let result = get(url).await?;
-    let content = Cursor::new(result.bytes().await?);
-    Ok(content)
+fn header_value(response: &Response, name: HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
 }
 
 pub fn parse_file_extension_from_image_url(url: &str) -> Result<String, CustomError> {
@@ -164,3 +282,35 @@ pub fn parse_file_extension_from_image_url(url: &str) -> Result<String, CustomEr
         .unwrap_or("jpg")
         .to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_handling::{config::default_config, memory::InMemoryLibrary};
+    use std::{net::TcpListener, time::Instant};
+
+    #[tokio::test]
+    async fn test_slow_feeds_fail_without_stalling_the_sync() {
+        // Accepts connections into its backlog but never answers them.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let library = InMemoryLibrary::default();
+        for n in 0..4 {
+            library
+                .add_feed(format!("http://{address}/feed-{n}.xml"))
+                .unwrap();
+        }
+        let limits = RefreshLimits {
+            concurrency: 4,
+            request_timeout: Duration::from_millis(300),
+        };
+        let started = Instant::now();
+        let http = HttpClient::new(&default_config()).unwrap();
+        let statuses = update_feeds(&library, &http, limits).await.unwrap();
+        assert!(started.elapsed() < Duration::from_millis(1000));
+        assert_eq!(statuses.len(), 4);
+        assert!(statuses
+            .iter()
+            .all(|(_, status)| matches!(status, FeedStatus::Failed(_))));
+    }
+}
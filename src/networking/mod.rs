@@ -1,2 +1,4 @@
+pub mod client;
+pub mod download_manager;
 pub mod downloads;
 pub mod feeds;
@@ -0,0 +1,127 @@
+use super::{
+    child, child_text, fallback_guid, is_element, parse_date, positive, with_itunes_details,
+    with_itunes_show_details, ParsedEpisode, ParsedFeed,
+};
+use roxmltree::Node;
+
+pub const NAMESPACE: &str = "http://www.w3.org/2005/Atom";
+/// Where `xml:lang` lives.
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Reads an Atom document from its `feed` element.
+pub fn parse(root: Node) -> ParsedFeed {
+    let feed = ParsedFeed {
+        title: child_text(root, Some(NAMESPACE), "title"),
+        image_url: child_text(root, Some(NAMESPACE), "logo")
+            .or_else(|| child_text(root, Some(NAMESPACE), "icon")),
+        author: child(root, Some(NAMESPACE), "author")
+            .and_then(|author| child_text(author, Some(NAMESPACE), "name")),
+        description: child_text(root, Some(NAMESPACE), "subtitle"),
+        website: link(root, "alternate")
+            .and_then(|link| link.attribute("href"))
+            .map(str::to_string),
+        language: root
+            .attribute((XML_NAMESPACE, "lang"))
+            .map(str::trim)
+            .filter(|language| !language.is_empty())
+            .map(str::to_string),
+        categories: root
+            .children()
+            .filter(|node| is_element(*node, Some(NAMESPACE), "category"))
+            .filter_map(|node| node.attribute("label").or(node.attribute("term")))
+            .map(str::trim)
+            .filter(|category| !category.is_empty())
+            .map(str::to_string)
+            .collect(),
+        copyright: child_text(root, Some(NAMESPACE), "rights"),
+        episodes: root
+            .children()
+            .filter(|node| is_element(*node, Some(NAMESPACE), "entry"))
+            .filter_map(parse_entry)
+            .collect(),
+        ..ParsedFeed::default()
+    };
+    with_itunes_show_details(root, feed)
+}
+
+/// The first `link` with the given `rel`. A link without one is `alternate`.
+fn link<'a, 'input>(parent: Node<'a, 'input>, rel: &str) -> Option<Node<'a, 'input>> {
+    parent.children().find(|node| {
+        is_element(*node, Some(NAMESPACE), "link")
+            && node.attribute("rel").unwrap_or("alternate") == rel
+    })
+}
+
+/// Entries are episodes when they have a `link rel="enclosure"`; any others are skipped.
+/// Entries without an `id` get `fallback_guid`.
+fn parse_entry(entry: Node) -> Option<ParsedEpisode> {
+    let enclosure = link(entry, "enclosure")?;
+    let url = enclosure.attribute("href")?;
+    let title = child_text(entry, Some(NAMESPACE), "title");
+    let date = child_text(entry, Some(NAMESPACE), "published")
+        .or_else(|| child_text(entry, Some(NAMESPACE), "updated"));
+    let page = link(entry, "alternate")
+        .and_then(|link| link.attribute("href"))
+        .map(str::to_string);
+    let guid = match child_text(entry, Some(NAMESPACE), "id") {
+        Some(id) => id,
+        None => fallback_guid(url, page.as_deref(), title.as_deref(), date.as_deref())?,
+    };
+    let episode = ParsedEpisode {
+        guid,
+        title,
+        date: date.and_then(|date| parse_date(&date)),
+        url: url.to_string(),
+        mime_type: enclosure.attribute("type").map(str::to_string),
+        description: child_text(entry, Some(NAMESPACE), "summary")
+            .or_else(|| child_text(entry, Some(NAMESPACE), "content")),
+        size_bytes: enclosure.attribute("length").and_then(positive),
+        link: page,
+        ..ParsedEpisode::default()
+    };
+    Some(with_itunes_details(entry, episode))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parsing::{parse_date, parse_feed};
+
+    #[test]
+    fn test_parse_atom() {
+        let feed = parse_feed(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+            <feed xmlns="http://www.w3.org/2005/Atom">
+                <title>An Atom Show</title>
+                <logo>https://example.com/logo.png</logo>
+                <entry>
+                    <id>urn:uuid:1</id>
+                    <title>First</title>
+                    <updated>2024-05-30T08:15:00+02:00</updated>
+                    <link rel="alternate" href="https://example.com/1"/>
+                    <link rel="enclosure" type="audio/mpeg" href="https://example.com/1.mp3" length="1200"/>
+                    <summary>Notes</summary>
+                </entry>
+                <entry>
+                    <id>urn:uuid:2</id>
+                    <title>A blog post</title>
+                    <link href="https://example.com/2"/>
+                </entry>
+            </feed>"#,
+        )
+        .unwrap();
+        assert_eq!(feed.title.as_deref(), Some("An Atom Show"));
+        assert_eq!(
+            feed.image_url.as_deref(),
+            Some("https://example.com/logo.png")
+        );
+        assert_eq!(feed.episodes.len(), 1);
+        let episode = &feed.episodes[0];
+        assert_eq!(episode.guid, "urn:uuid:1");
+        assert_eq!(episode.url, "https://example.com/1.mp3");
+        assert_eq!(episode.mime_type.as_deref(), Some("audio/mpeg"));
+        assert_eq!(episode.size_bytes, Some(1200));
+        assert_eq!(episode.description.as_deref(), Some("Notes"));
+        assert_eq!(episode.link.as_deref(), Some("https://example.com/1"));
+        assert_eq!(episode.date, parse_date("2024-05-30T06:15:00Z"));
+    }
+}
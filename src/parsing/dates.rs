@@ -0,0 +1,256 @@
+use time::{
+    format_description::well_known::{Rfc2822, Rfc3339},
+    Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset,
+};
+
+/// Zone abbreviations seen in the wild, with their offsets in minutes. RFC 2822 only allows the
+/// North American ones, but feeds use whatever their server's locale prints.
+const ZONE_NAMES: &[(&str, i16)] = &[
+    ("UT", 0),
+    ("UTC", 0),
+    ("GMT", 0),
+    ("Z", 0),
+    ("WET", 0),
+    ("WEST", 60),
+    ("BST", 60),
+    ("CET", 60),
+    ("CEST", 120),
+    ("MET", 60),
+    ("MEST", 120),
+    ("EET", 120),
+    ("EEST", 180),
+    ("MSK", 180),
+    ("IST", 330),
+    ("JST", 540),
+    ("KST", 540),
+    ("AWST", 480),
+    ("ACST", 570),
+    ("ACDT", 630),
+    ("AEST", 600),
+    ("AEDT", 660),
+    ("NZST", 720),
+    ("NZDT", 780),
+    ("AST", -240),
+    ("ADT", -180),
+    ("EST", -300),
+    ("EDT", -240),
+    ("CST", -360),
+    ("CDT", -300),
+    ("MST", -420),
+    ("MDT", -360),
+    ("PST", -480),
+    ("PDT", -420),
+    ("AKST", -540),
+    ("AKDT", -480),
+    ("HST", -600),
+];
+
+/// Reads a feed's date, in UTC. Takes RFC 2822 and RFC 3339 as well as the ways feeds get them
+/// wrong: a wrong or missing weekday, full or oddly cased month names, two-digit years, no
+/// seconds or no time at all, named time zones and no time zone (read as UTC).
+pub fn parse_date(text: &str) -> Option<OffsetDateTime> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    OffsetDateTime::parse(text, &Rfc3339)
+        .or_else(|_| OffsetDateTime::parse(text, &Rfc2822))
+        .ok()
+        .or_else(|| parse_loose_rfc2822(text))
+        .or_else(|| parse_loose_iso(text))
+        .map(|date| date.to_offset(UtcOffset::UTC))
+}
+
+/// `[weekday,] day month year [time] [zone]`, or with the month first, ignoring comments.
+fn parse_loose_rfc2822(text: &str) -> Option<OffsetDateTime> {
+    let without_comments: String = text
+        .split('(')
+        .map(|part| part.split_once(')').map_or(part, |(_, rest)| rest))
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(',', " ");
+    let mut tokens = without_comments.split_whitespace().peekable();
+    if tokens
+        .peek()
+        .is_some_and(|token| token.chars().all(char::is_alphabetic) && month(token).is_none())
+    {
+        tokens.next();
+    }
+    let (first, second) = (tokens.next()?, tokens.next()?);
+    let (day, month) = match (month(first), month(second)) {
+        (None, Some(month)) => (first.parse().ok()?, month),
+        (Some(month), None) => (second.parse().ok()?, month),
+        _ => return None,
+    };
+    let year = match tokens.next()? {
+        year if year.len() == 2 => match year.parse::<i32>().ok()? {
+            short @ 0..=49 => 2000 + short,
+            short => 1900 + short,
+        },
+        year if year.len() == 4 => year.parse().ok()?,
+        _ => return None,
+    };
+    let date = Date::from_calendar_date(year, month, day).ok()?;
+    let (time, offset) = match tokens.next() {
+        Some(token) if token.contains(':') => (clock(token)?, tokens.next()),
+        token => (Time::MIDNIGHT, token),
+    };
+    let offset = match offset {
+        Some(zone) => utc_offset(zone)?,
+        None => UtcOffset::UTC,
+    };
+    Some(PrimitiveDateTime::new(date, time).assume_offset(offset))
+}
+
+/// `YYYY-MM-DD`, optionally followed by a time and zone after a `T` or a space.
+fn parse_loose_iso(text: &str) -> Option<OffsetDateTime> {
+    let (date, rest) = text.split_at_checked(10)?;
+    let mut parts = date.split('-');
+    let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return None;
+    }
+    let date = Date::from_calendar_date(
+        year.parse().ok()?,
+        Month::try_from(month.parse::<u8>().ok()?).ok()?,
+        day.parse().ok()?,
+    )
+    .ok()?;
+    let rest = rest.trim_start_matches(['T', 't', ' ']);
+    if rest.is_empty() {
+        return Some(date.midnight().assume_utc());
+    }
+    let zone_start = rest
+        .find(|c: char| c == '+' || c == '-' || c == ' ' || c.is_alphabetic())
+        .unwrap_or(rest.len());
+    let (time, zone) = rest.split_at(zone_start);
+    let zone = zone.trim();
+    let offset = match zone {
+        "" => UtcOffset::UTC,
+        zone => utc_offset(zone)?,
+    };
+    Some(PrimitiveDateTime::new(date, clock(time)?).assume_offset(offset))
+}
+
+/// The month named by the first three letters of `token`, in any case.
+fn month(token: &str) -> Option<Month> {
+    let prefix = token.get(..3)?.to_ascii_lowercase();
+    let months = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let index = months.iter().position(|name| *name == prefix)?;
+    Month::try_from(index as u8 + 1).ok()
+}
+
+/// `H:MM`, `H:MM:SS` or `H:MM:SS.fff`. Fractions are dropped and a leap second becomes :59.
+fn clock(token: &str) -> Option<Time> {
+    let mut parts = token.split(':');
+    let hour = parts.next()?.parse().ok()?;
+    let minute = parts.next()?.parse().ok()?;
+    let second = match parts.next() {
+        Some(second) => second.split('.').next()?.parse::<u8>().ok()?.min(59),
+        None => 0,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    Time::from_hms(hour, minute, second).ok()
+}
+
+/// A numeric offset (`+0200`, `+02:00`, `+02`, `-05:00:00`), a zone name, or a name with an
+/// offset after it (`GMT+2`). Unknown names are read as UTC rather than losing the date.
+fn utc_offset(zone: &str) -> Option<UtcOffset> {
+    let numeric = zone.find(['+', '-']).map(|start| &zone[start..]);
+    match numeric {
+        Some(numeric) => {
+            let sign: i32 = if numeric.starts_with('-') { -1 } else { 1 };
+            let digits: String = numeric[1..].chars().filter(|c| *c != ':').collect();
+            if !digits.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            let (hours, minutes) = match digits.len() {
+                1 | 2 => (digits.parse::<i32>().ok()?, 0),
+                4 | 6 => (digits[..2].parse().ok()?, digits[2..4].parse().ok()?),
+                _ => return None,
+            };
+            UtcOffset::from_whole_seconds(sign * (hours * 3600 + minutes * 60)).ok()
+        }
+        None => {
+            let minutes = ZONE_NAMES
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(zone))
+                .map_or(0, |(_, minutes)| *minutes);
+            UtcOffset::from_whole_seconds(i32::from(minutes) * 60).ok()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(day: u8, hour: u8, minute: u8) -> OffsetDateTime {
+        Date::from_calendar_date(2024, Month::May, day)
+            .unwrap()
+            .with_hms(hour, minute, 0)
+            .unwrap()
+            .assume_utc()
+    }
+
+    #[test]
+    fn test_parse_date_variants() {
+        let expected = utc(30, 13, 45);
+        for text in [
+            "Thu, 30 May 2024 13:45:00 +0000",
+            "Thu, 30 May 2024 13:45:00 GMT",
+            "Mon, 30 May 2024 13:45:00 GMT",
+            "30 May 2024 13:45 UT",
+            "Thursday, 30 May 2024 13:45:00 Z",
+            "thu, 30 may 24 09:45:00 EDT",
+            "Thu, 30 Mayo 2024 15:45:00 CEST",
+            "Thu,30 May 2024 15:45:00 +02:00",
+            "Thu, 30 May 2024 13:45:00 (Coordinated Universal Time)",
+            "May 30, 2024 13:45:00",
+            "Thu, 30 May 2024 13:45:00 +0000 GMT",
+            "2024-05-30T13:45:00Z",
+            "2024-05-30T15:45:00.123+02:00",
+            "2024-05-30 13:45:00",
+            "2024-05-30 13:45:00.0 +00:00:00",
+            "2024-05-30T08:45:00 EST",
+            "  Thu, 30 May 2024 13:45:00 +0000\n",
+        ] {
+            let parsed = parse_date(text).map(|date| date.replace_nanosecond(0).unwrap());
+            assert_eq!(parsed, Some(expected), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn test_parse_date_without_time_or_zone() {
+        assert_eq!(parse_date("Thu, 30 May 2024"), Some(utc(30, 0, 0)));
+        assert_eq!(parse_date("2024-05-30"), Some(utc(30, 0, 0)));
+        assert_eq!(
+            parse_date("Thu, 30 May 2024 13:45:00 XYZT"),
+            Some(utc(30, 13, 45))
+        );
+        assert_eq!(
+            parse_date("Fri, 31 May 2024 01:30:00 +1130"),
+            Some(utc(30, 14, 0))
+        );
+    }
+
+    #[test]
+    fn test_parse_date_rejects_nonsense() {
+        for text in [
+            "",
+            "   ",
+            "soon",
+            "Thu, 31 Feb 2024 13:45:00 +0000",
+            "Thu, 30 May 2024 25:45:00 +0000",
+            "2024-13-30T13:45:00Z",
+            "30 May",
+            "2024-05-30Tnoon",
+        ] {
+            assert_eq!(parse_date(text), None, "{text:?}");
+        }
+    }
+}
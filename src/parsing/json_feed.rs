@@ -0,0 +1,162 @@
+use super::{fallback_guid, parse_date, ParseError, ParsedEpisode, ParsedFeed};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// The parts of a JSON Feed (https://jsonfeed.org/version/1.1) that Castiron uses.
+#[derive(Deserialize)]
+struct JsonFeed {
+    title: Option<String>,
+    icon: Option<String>,
+    favicon: Option<String>,
+    description: Option<String>,
+    home_page_url: Option<String>,
+    language: Option<String>,
+    /// Version 1.1 lists authors; 1.0 had a single one.
+    #[serde(default)]
+    authors: Vec<Author>,
+    author: Option<Author>,
+    #[serde(default)]
+    items: Vec<Item>,
+}
+
+#[derive(Deserialize)]
+struct Author {
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Item {
+    /// Meant to be a string, but some generators write numbers or leave it out.
+    #[serde(default)]
+    id: Value,
+    url: Option<String>,
+    title: Option<String>,
+    content_text: Option<String>,
+    content_html: Option<String>,
+    summary: Option<String>,
+    image: Option<String>,
+    date_published: Option<String>,
+    date_modified: Option<String>,
+    #[serde(default)]
+    attachments: Vec<Attachment>,
+}
+
+#[derive(Deserialize)]
+struct Attachment {
+    url: String,
+    mime_type: Option<String>,
+    /// Numbers, though some generators quote them; anything else is ignored rather than
+    /// failing the whole feed.
+    #[serde(default)]
+    size_in_bytes: Value,
+    #[serde(default)]
+    duration_in_seconds: Value,
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+    .filter(|number| number.is_finite() && *number > 0.0)
+}
+
+pub fn parse(content: &str) -> Result<ParsedFeed, ParseError> {
+    let feed: JsonFeed = serde_json::from_str(content)?;
+    let names: Vec<String> = feed
+        .authors
+        .into_iter()
+        .chain(feed.author)
+        .filter_map(|author| author.name)
+        .filter(|name| !name.trim().is_empty())
+        .collect();
+    Ok(ParsedFeed {
+        title: feed.title,
+        image_url: feed.icon.or(feed.favicon),
+        author: Some(names.join(", ")).filter(|names| !names.is_empty()),
+        description: feed.description,
+        website: feed.home_page_url,
+        language: feed.language,
+        episodes: feed.items.into_iter().filter_map(parse_item).collect(),
+        ..ParsedFeed::default()
+    })
+}
+
+/// Items are episodes when they have an attachment; the first one is used. Items without a
+/// usable `id` get `fallback_guid`.
+fn parse_item(item: Item) -> Option<ParsedEpisode> {
+    let attachment = item.attachments.into_iter().next()?;
+    let date = item.date_published.or(item.date_modified);
+    let guid = match item.id {
+        Value::String(id) if !id.trim().is_empty() => id,
+        Value::Number(id) => id.to_string(),
+        _ => fallback_guid(
+            &attachment.url,
+            item.url.as_deref(),
+            item.title.as_deref(),
+            date.as_deref(),
+        )?,
+    };
+    let non_empty = |text: Option<String>| text.filter(|text| !text.trim().is_empty());
+    Some(ParsedEpisode {
+        guid,
+        title: item.title,
+        date: date.and_then(|date| parse_date(&date)),
+        url: attachment.url,
+        mime_type: attachment.mime_type,
+        description: non_empty(item.summary)
+            .or_else(|| non_empty(item.content_text))
+            .or_else(|| non_empty(item.content_html)),
+        duration_seconds: number(&attachment.duration_in_seconds).map(|seconds| seconds as u32),
+        size_bytes: number(&attachment.size_in_bytes).map(|size| size as u64),
+        link: non_empty(item.url),
+        image_url: non_empty(item.image),
+        ..ParsedEpisode::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parsing::{parse_date, parse_feed};
+
+    #[test]
+    fn test_parse_json_feed() {
+        let feed = parse_feed(
+            r#"{
+                "version": "https://jsonfeed.org/version/1.1",
+                "title": "A JSON Show",
+                "icon": "https://example.com/icon.png",
+                "items": [
+                    {
+                        "id": 42,
+                        "title": "Answers",
+                        "date_published": "2024-05-30T08:15:00Z",
+                        "attachments": [
+                            {
+                                "url": "https://example.com/42.m4a",
+                                "mime_type": "audio/aac",
+                                "size_in_bytes": "4200",
+                                "duration_in_seconds": 2520.5
+                            }
+                        ]
+                    },
+                    {"id": "post", "content_text": "No audio here"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(feed.title.as_deref(), Some("A JSON Show"));
+        assert_eq!(
+            feed.image_url.as_deref(),
+            Some("https://example.com/icon.png")
+        );
+        assert_eq!(feed.episodes.len(), 1);
+        let episode = &feed.episodes[0];
+        assert_eq!(episode.guid, "42");
+        assert_eq!(episode.url, "https://example.com/42.m4a");
+        assert_eq!(episode.size_bytes, Some(4200));
+        assert_eq!(episode.duration_seconds, Some(2520));
+        assert_eq!(episode.date, parse_date("2024-05-30T08:15:00Z"));
+    }
+}
@@ -0,0 +1,446 @@
+mod atom;
+mod dates;
+mod json_feed;
+mod rss;
+
+pub use dates::parse_date;
+use roxmltree::{Document, Node};
+use std::fmt;
+use time::OffsetDateTime;
+
+/// What a feed says about the show and its episodes, whichever format it came in.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParsedFeed {
+    pub title: Option<String>,
+    pub image_url: Option<String>,
+    pub author: Option<String>,
+    /// What the show is about, which is often HTML.
+    pub description: Option<String>,
+    /// The show's web page.
+    pub website: Option<String>,
+    pub language: Option<String>,
+    /// Subcategories are written after their parent, as in `Arts › Food`.
+    pub categories: Vec<String>,
+    pub explicit: Option<bool>,
+    /// `episodic` or `serial`, lowercased.
+    pub show_type: Option<String>,
+    pub copyright: Option<String>,
+    pub episodes: Vec<ParsedEpisode>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParsedEpisode {
+    pub guid: String,
+    pub title: Option<String>,
+    /// In UTC, when the feed gave a date `parse_date` could read.
+    pub date: Option<OffsetDateTime>,
+    pub url: String,
+    pub mime_type: Option<String>,
+    /// Show notes as the feed gave them, which is often HTML.
+    pub description: Option<String>,
+    pub duration_seconds: Option<u32>,
+    /// The enclosure's length in bytes. Feeds that write 0 for "unknown" read as `None`.
+    pub size_bytes: Option<u64>,
+    pub season: Option<u32>,
+    pub episode_number: Option<u32>,
+    /// `full`, `trailer` or `bonus`, lowercased.
+    pub episode_type: Option<String>,
+    /// The episode's web page.
+    pub link: Option<String>,
+    /// Artwork for this episode alone, when it has its own.
+    pub image_url: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ParseError {
+    Xml(roxmltree::Error),
+    Json(serde_json::Error),
+    /// A document that is not RSS, Atom or JSON Feed, named by its root element.
+    UnsupportedFormat(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Xml(e) => write!(f, "{e}"),
+            ParseError::Json(e) => write!(f, "{e}"),
+            ParseError::UnsupportedFormat(root) => {
+                write!(f, "not an RSS, Atom or JSON feed (root element `{root}`)")
+            }
+        }
+    }
+}
+
+impl From<roxmltree::Error> for ParseError {
+    fn from(err: roxmltree::Error) -> Self {
+        ParseError::Xml(err)
+    }
+}
+
+impl From<serde_json::Error> for ParseError {
+    fn from(err: serde_json::Error) -> Self {
+        ParseError::Json(err)
+    }
+}
+
+/// Reads an RSS, Atom or JSON Feed document, telling them apart by their content rather than
+/// trusting the server's Content-Type.
+///
+/// This does no I/O and depends on nothing else in the crate, which lets the fuzz target in
+/// `fuzz/` build it on its own. Malformed input is an error, never a panic.
+pub fn parse_feed(content: &str) -> Result<ParsedFeed, ParseError> {
+    let content = content.trim_start_matches('\u{feff}').trim_start();
+    if content.starts_with('{') {
+        return json_feed::parse(content);
+    }
+    let doc = Document::parse(content)?;
+    let root = doc.root_element();
+    match (root.tag_name().namespace(), root.tag_name().name()) {
+        (None, "rss") => Ok(rss::parse(root)),
+        (Some(atom::NAMESPACE), "feed") => Ok(atom::parse(root)),
+        (_, name) => Err(ParseError::UnsupportedFormat(name.to_string())),
+    }
+}
+
+fn is_element(node: Node, namespace: Option<&str>, name: &str) -> bool {
+    node.is_element() && node.tag_name().namespace() == namespace && node.tag_name().name() == name
+}
+
+fn child<'a, 'input>(
+    node: Node<'a, 'input>,
+    namespace: Option<&str>,
+    name: &str,
+) -> Option<Node<'a, 'input>> {
+    node.children()
+        .find(|child| is_element(*child, namespace, name))
+}
+
+/// Apple's podcast namespace, which RSS feeds and some Atom feeds use for episode details.
+const ITUNES: &str = "http://www.itunes.com/dtds/podcast-1.0.dtd";
+
+/// Fills in what an item's `itunes:` elements say, leaving anything the format's own elements
+/// already gave alone.
+fn with_itunes_details(item: Node, episode: ParsedEpisode) -> ParsedEpisode {
+    let text = |name| child_text(item, Some(ITUNES), name);
+    ParsedEpisode {
+        description: episode.description.or_else(|| text("summary")),
+        duration_seconds: text("duration").and_then(|duration| parse_duration(&duration)),
+        season: text("season").and_then(|season| positive(&season)),
+        episode_number: text("episode").and_then(|number| positive(&number)),
+        episode_type: text("episodeType").map(|kind| kind.to_lowercase()),
+        image_url: child(item, Some(ITUNES), "image")
+            .and_then(|image| image.attribute("href"))
+            .map(str::trim)
+            .filter(|href| !href.is_empty())
+            .map(str::to_string),
+        ..episode
+    }
+}
+
+/// Fills in what a channel's `itunes:` elements say about the show. Its author wins over the
+/// format's own, which in RSS is an email address; anything else the format gave is kept.
+fn with_itunes_show_details(channel: Node, feed: ParsedFeed) -> ParsedFeed {
+    let text = |name| child_text(channel, Some(ITUNES), name);
+    let mut categories = feed.categories;
+    for category in itunes_categories(channel) {
+        if !categories.contains(&category) {
+            categories.push(category);
+        }
+    }
+    ParsedFeed {
+        author: text("author").or(feed.author),
+        description: feed.description.or_else(|| text("summary")),
+        categories,
+        explicit: text("explicit").and_then(|explicit| match explicit.to_lowercase().as_str() {
+            "true" | "yes" | "explicit" => Some(true),
+            "false" | "no" | "clean" => Some(false),
+            _ => None,
+        }),
+        show_type: text("type").map(|kind| kind.to_lowercase()),
+        ..feed
+    }
+}
+
+/// `itunes:category` elements, whose subcategories are nested inside them.
+fn itunes_categories(channel: Node) -> Vec<String> {
+    let named = |node: Node| is_element(node, Some(ITUNES), "category");
+    let name = |node: Node| {
+        node.attribute("text")
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+    };
+    let mut categories = Vec::new();
+    for category in channel.children().filter(|node| named(*node)) {
+        let Some(parent) = name(category) else {
+            continue;
+        };
+        let subcategories: Vec<String> = category
+            .children()
+            .filter(|node| named(*node))
+            .filter_map(name)
+            .collect();
+        match subcategories.is_empty() {
+            true => categories.push(parent),
+            false => categories.extend(
+                subcategories
+                    .into_iter()
+                    .map(|subcategory| format!("{parent} › {subcategory}")),
+            ),
+        }
+    }
+    categories
+}
+
+/// `itunes:duration`, which may be plain seconds or `MM:SS` or `HH:MM:SS`, possibly with a
+/// fraction on the seconds.
+fn parse_duration(text: &str) -> Option<u32> {
+    let mut seconds: u32 = 0;
+    let parts: Vec<&str> = text.trim().split(':').collect();
+    if parts.len() > 3 {
+        return None;
+    }
+    for part in parts {
+        let whole = part.split('.').next()?.trim();
+        let value: u32 = whole.parse().ok()?;
+        seconds = seconds.checked_mul(60)?.checked_add(value)?;
+    }
+    Some(seconds)
+}
+
+/// A positive count, such as a season, an episode number or a size. Zero is how many feeds
+/// write "unknown".
+fn positive<T: std::str::FromStr + Default + PartialEq>(text: &str) -> Option<T> {
+    text.trim()
+        .parse()
+        .ok()
+        .filter(|value| *value != T::default())
+}
+
+/// 64-bit FNV-1a. Identities and file names derived from it have to stay the same between
+/// builds, which the standard library's hashers do not promise.
+pub fn stable_hash(value: &str) -> u64 {
+    value.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Who an episode is when its feed leaves out the guid: its enclosure URL, then its link, then
+/// a hash of its title and date. This has to come out the same on every refresh, or the
+/// episode would be added to the library again each time.
+fn fallback_guid(
+    enclosure_url: &str,
+    link: Option<&str>,
+    title: Option<&str>,
+    date: Option<&str>,
+) -> Option<String> {
+    [Some(enclosure_url), link]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .find(|candidate| !candidate.is_empty())
+        .map(str::to_string)
+        .or_else(|| {
+            let identity = format!("{}\n{}", title?, date.unwrap_or_default());
+            Some(format!("title-date:{:016x}", stable_hash(&identity)))
+        })
+}
+
+/// The trimmed text of a child element, if it has any.
+fn child_text(node: Node, namespace: Option<&str>, name: &str) -> Option<String> {
+    child(node, namespace, name)
+        .and_then(|child| child.text())
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, path::PathBuf};
+
+    fn fixtures_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/feeds")
+    }
+
+    fn fixture(name: &str) -> String {
+        fs::read_to_string(fixtures_dir().join(name)).unwrap()
+    }
+
+    #[test]
+    fn test_fixtures_parse_or_fail_cleanly() {
+        // How many episodes each fixture holds, or `None` when it should be rejected.
+        let expected = [
+            ("atom_podcast.xml", Some(2)),
+            ("html_error_page.xml", None),
+            ("json_feed.json", Some(3)),
+            ("json_feed_wrong_shape.json", None),
+            ("rss_bom_leading_whitespace.xml", Some(1)),
+            ("rss_empty_elements.xml", Some(3)),
+            ("rss_itunes.xml", Some(2)),
+            ("rss_no_channel.xml", Some(0)),
+            ("rss_odd_dates.xml", Some(5)),
+            ("rss_truncated.xml", None),
+        ];
+        let mut on_disk: Vec<String> = fs::read_dir(fixtures_dir())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        on_disk.sort();
+        assert_eq!(
+            on_disk,
+            expected.map(|(name, _)| name.to_string()),
+            "every fixture needs an expectation"
+        );
+        for (name, episodes) in expected {
+            let parsed = parse_feed(&fixture(name));
+            assert_eq!(
+                parsed.as_ref().ok().map(|feed| feed.episodes.len()),
+                episodes,
+                "{name}: {parsed:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_empty_elements_read_as_missing() {
+        let feed = parse_feed(&fixture("rss_empty_elements.xml")).unwrap();
+        assert_eq!(feed.title, None);
+        assert_eq!(feed.image_url, None);
+        assert_eq!(
+            feed.episodes[0],
+            ParsedEpisode {
+                guid: String::from("empty-title-and-date"),
+                title: None,
+                date: None,
+                url: String::from("https://media.example.com/1.m4a"),
+                mime_type: None,
+                ..ParsedEpisode::default()
+            }
+        );
+        assert_eq!(feed.episodes[1].guid, "https://media.example.com/2.mp3");
+        assert_eq!(feed.episodes[2].guid, "whitespace-around-guid");
+    }
+
+    #[test]
+    fn test_odd_dates_are_read_or_left_out() {
+        let feed = parse_feed(&fixture("rss_odd_dates.xml")).unwrap();
+        let dates: Vec<Option<OffsetDateTime>> =
+            feed.episodes.iter().map(|episode| episode.date).collect();
+        assert_eq!(
+            dates,
+            vec![
+                parse_date("2024-06-04T16:00:00Z"),
+                parse_date("2024-06-03T07:00:00Z"),
+                parse_date("2024-06-02T00:00:00Z"),
+                parse_date("2024-06-01T12:00:00Z"),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fallback_guid_order() {
+        let link = Some("https://example.com/episodes/1");
+        assert_eq!(
+            fallback_guid("https://example.com/1.mp3", link, Some("One"), None).as_deref(),
+            Some("https://example.com/1.mp3")
+        );
+        assert_eq!(fallback_guid(" ", link, Some("One"), None).as_deref(), link);
+        let hashed = fallback_guid("", None, Some("One"), Some("Thu, 30 May 2024")).unwrap();
+        assert!(hashed.starts_with("title-date:"));
+        assert_eq!(
+            fallback_guid("", Some(""), Some("One"), Some("Thu, 30 May 2024")),
+            Some(hashed.to_owned())
+        );
+        assert_ne!(
+            fallback_guid("", None, Some("One"), Some("Fri, 31 May 2024")),
+            Some(hashed)
+        );
+        assert_eq!(
+            fallback_guid("", None, None, Some("Thu, 30 May 2024")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_show_details_from_each_format() {
+        let rss = parse_feed(&fixture("rss_itunes.xml")).unwrap();
+        assert_eq!(rss.author.as_deref(), Some("Example Media"));
+        assert_eq!(
+            rss.description.as_deref(),
+            Some("<p>News, <b>analysis</b> &amp; interviews every week.</p>")
+        );
+        assert_eq!(rss.website.as_deref(), Some("https://weekly.example.com"));
+        assert_eq!(rss.language.as_deref(), Some("en-us"));
+        assert_eq!(rss.categories, vec!["News › Daily News"]);
+        assert_eq!(rss.explicit, Some(false));
+        assert_eq!(rss.show_type, None);
+        assert_eq!(rss.copyright.as_deref(), Some("© 2024 Example Media"));
+
+        let atom = parse_feed(&fixture("atom_podcast.xml")).unwrap();
+        assert_eq!(atom.author.as_deref(), Some("Sam Field"));
+        assert_eq!(atom.description.as_deref(), Some("Sounds from places."));
+        assert_eq!(atom.language.as_deref(), Some("en"));
+        assert_eq!(atom.website, None);
+
+        let json = parse_feed(&fixture("json_feed.json")).unwrap();
+        assert_eq!(json.author.as_deref(), Some("Alex and Jo"));
+        assert_eq!(
+            json.website.as_deref(),
+            Some("https://kitchen.example.net/")
+        );
+    }
+
+    #[test]
+    fn test_itunes_show_details() {
+        let feed = parse_feed(
+            r#"<rss version="2.0" xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd">
+                <channel>
+                    <managingEditor>editor@example.com (Editor)</managingEditor>
+                    <itunes:author>The Host</itunes:author>
+                    <itunes:summary>From iTunes</itunes:summary>
+                    <category>Cooking</category>
+                    <itunes:category text="Arts">
+                        <itunes:category text="Food"/>
+                        <itunes:category text="Design"/>
+                    </itunes:category>
+                    <itunes:category text="Leisure"/>
+                    <itunes:category text=""/>
+                    <itunes:explicit>yes</itunes:explicit>
+                    <itunes:type>Serial</itunes:type>
+                </channel>
+            </rss>"#,
+        )
+        .unwrap();
+        assert_eq!(feed.author.as_deref(), Some("The Host"));
+        assert_eq!(feed.description.as_deref(), Some("From iTunes"));
+        assert_eq!(
+            feed.categories,
+            vec!["Cooking", "Arts › Food", "Arts › Design", "Leisure"]
+        );
+        assert_eq!(feed.explicit, Some(true));
+        assert_eq!(feed.show_type.as_deref(), Some("serial"));
+    }
+
+    #[test]
+    fn test_detects_each_format() {
+        let rss = r#"<?xml version="1.0"?><rss version="2.0"><channel><title>RSS Show</title></channel></rss>"#;
+        let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>Atom Show</title></feed>"#;
+        let json = "\u{feff}  {\"version\": \"https://jsonfeed.org/version/1.1\", \"title\": \"JSON Show\", \"items\": []}";
+        assert_eq!(parse_feed(rss).unwrap().title.as_deref(), Some("RSS Show"));
+        assert_eq!(
+            parse_feed(atom).unwrap().title.as_deref(),
+            Some("Atom Show")
+        );
+        assert_eq!(
+            parse_feed(json).unwrap().title.as_deref(),
+            Some("JSON Show")
+        );
+        assert!(matches!(
+            parse_feed("<html><body/></html>"),
+            Err(ParseError::UnsupportedFormat(_))
+        ));
+    }
+}
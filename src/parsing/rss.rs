@@ -0,0 +1,165 @@
+use super::{
+    child, child_text, fallback_guid, is_element, parse_date, positive, with_itunes_details,
+    with_itunes_show_details, ParsedEpisode, ParsedFeed,
+};
+use roxmltree::Node;
+
+/// Dublin Core, whose `dc:date` some feeds give instead of `pubDate`.
+const DUBLIN_CORE: &str = "http://purl.org/dc/elements/1.1/";
+/// Where `content:encoded`, the long form of an item's show notes, comes from.
+const CONTENT: &str = "http://purl.org/rss/1.0/modules/content/";
+
+/// Reads an RSS 2.0 document from its `rss` element.
+pub fn parse(root: Node) -> ParsedFeed {
+    let Some(channel) = child(root, None, "channel") else {
+        return ParsedFeed::default();
+    };
+    let feed = ParsedFeed {
+        title: child_text(channel, None, "title"),
+        image_url: child(channel, None, "image").and_then(|image| child_text(image, None, "url")),
+        author: child_text(channel, None, "managingEditor"),
+        description: child_text(channel, None, "description"),
+        website: child_text(channel, None, "link"),
+        language: child_text(channel, None, "language"),
+        categories: channel
+            .children()
+            .filter(|node| is_element(*node, None, "category"))
+            .filter_map(|node| node.text())
+            .map(str::trim)
+            .filter(|category| !category.is_empty())
+            .map(str::to_string)
+            .collect(),
+        copyright: child_text(channel, None, "copyright"),
+        episodes: channel
+            .children()
+            .filter(|node| is_element(*node, None, "item"))
+            .filter_map(parse_item)
+            .collect(),
+        ..ParsedFeed::default()
+    };
+    with_itunes_show_details(channel, feed)
+}
+
+/// Items without an enclosure URL are skipped. Those without a guid get `fallback_guid`.
+fn parse_item(item: Node) -> Option<ParsedEpisode> {
+    let enclosure = child(item, None, "enclosure")?;
+    let url = enclosure.attribute("url")?;
+    let title = child_text(item, None, "title");
+    let date =
+        child_text(item, None, "pubDate").or_else(|| child_text(item, Some(DUBLIN_CORE), "date"));
+    let link = child_text(item, None, "link");
+    let guid = match child_text(item, None, "guid") {
+        Some(guid) => guid,
+        None => fallback_guid(url, link.as_deref(), title.as_deref(), date.as_deref())?,
+    };
+    let episode = ParsedEpisode {
+        guid,
+        title,
+        date: date.and_then(|date| parse_date(&date)),
+        url: url.to_string(),
+        mime_type: enclosure.attribute("type").map(str::to_string),
+        // `content:encoded` carries the full show notes where `description` is often a teaser.
+        description: child_text(item, Some(CONTENT), "encoded")
+            .or_else(|| child_text(item, None, "description")),
+        size_bytes: enclosure.attribute("length").and_then(positive),
+        link,
+        ..ParsedEpisode::default()
+    };
+    Some(with_itunes_details(item, episode))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parsing::{parse_date, parse_feed};
+
+    #[test]
+    fn test_parse_rss() {
+        let feed = parse_feed(
+            r#"<rss version="2.0" xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd">
+                <channel>
+                    <title>A Show</title>
+                    <image><url>https://example.com/cover.jpg</url><title>Cover</title></image>
+                    <item>
+                        <itunes:title>Not this one</itunes:title>
+                        <title><![CDATA[Episode & One]]></title>
+                        <pubDate>Thu, 30 May 2024 00:00:00 +0000</pubDate>
+                        <guid>ep-1</guid>
+                        <enclosure url="https://example.com/1.ogg" type="audio/ogg" length="1"/>
+                    </item>
+                    <item><title>No enclosure</title><guid>ep-2</guid></item>
+                    <item>
+                        <title>No guid</title>
+                        <enclosure url=" https://example.com/3.mp3" type="audio/mpeg"/>
+                    </item>
+                </channel>
+            </rss>"#,
+        )
+        .unwrap();
+        assert_eq!(feed.title.as_deref(), Some("A Show"));
+        assert_eq!(
+            feed.image_url.as_deref(),
+            Some("https://example.com/cover.jpg")
+        );
+        assert_eq!(feed.episodes.len(), 2);
+        assert_eq!(feed.episodes[1].guid, "https://example.com/3.mp3");
+        let episode = &feed.episodes[0];
+        assert_eq!(episode.title.as_deref(), Some("Episode & One"));
+        assert_eq!(episode.guid, "ep-1");
+        assert_eq!(episode.mime_type.as_deref(), Some("audio/ogg"));
+        assert_eq!(episode.date, parse_date("2024-05-30T00:00:00Z"));
+    }
+
+    #[test]
+    fn test_parse_rss_episode_details() {
+        let feed = parse_feed(
+            r#"<rss version="2.0"
+                    xmlns:itunes="http://www.itunes.com/dtds/podcast-1.0.dtd"
+                    xmlns:content="http://purl.org/rss/1.0/modules/content/">
+                <channel>
+                    <item>
+                        <guid>detailed</guid>
+                        <link>https://example.com/episodes/detailed</link>
+                        <description>Teaser</description>
+                        <content:encoded><![CDATA[<p>Long notes</p>]]></content:encoded>
+                        <itunes:duration>1:02:03</itunes:duration>
+                        <itunes:season>2</itunes:season>
+                        <itunes:episode>14</itunes:episode>
+                        <itunes:episodeType>Bonus</itunes:episodeType>
+                        <itunes:image href="https://example.com/14.jpg"/>
+                        <enclosure url="https://example.com/14.mp3" length="38000000"/>
+                    </item>
+                    <item>
+                        <guid>sparse</guid>
+                        <description>Short notes</description>
+                        <itunes:summary>Not these</itunes:summary>
+                        <itunes:duration>2712</itunes:duration>
+                        <itunes:season>0</itunes:season>
+                        <enclosure url="https://example.com/15.mp3" length="0"/>
+                    </item>
+                </channel>
+            </rss>"#,
+        )
+        .unwrap();
+        let detailed = &feed.episodes[0];
+        assert_eq!(detailed.description.as_deref(), Some("<p>Long notes</p>"));
+        assert_eq!(detailed.duration_seconds, Some(3723));
+        assert_eq!(detailed.size_bytes, Some(38_000_000));
+        assert_eq!(detailed.season, Some(2));
+        assert_eq!(detailed.episode_number, Some(14));
+        assert_eq!(detailed.episode_type.as_deref(), Some("bonus"));
+        assert_eq!(
+            detailed.link.as_deref(),
+            Some("https://example.com/episodes/detailed")
+        );
+        assert_eq!(
+            detailed.image_url.as_deref(),
+            Some("https://example.com/14.jpg")
+        );
+        let sparse = &feed.episodes[1];
+        assert_eq!(sparse.description.as_deref(), Some("Short notes"));
+        assert_eq!(sparse.duration_seconds, Some(2712));
+        assert_eq!(sparse.size_bytes, None);
+        assert_eq!(sparse.season, None);
+        assert_eq!(sparse.image_url, None);
+    }
+}
@@ -3,4 +3,72 @@ use serde::{Deserialize, Serialize};
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CastironConfig {
     pub theme: String,
+    /// An episode counts as played once playback gets this close to its end.
+    #[serde(default = "default_mark_played_threshold_seconds")]
+    pub mark_played_threshold_seconds: u32,
+    #[serde(default = "default_skip_back_seconds")]
+    pub skip_back_seconds: u32,
+    #[serde(default = "default_skip_forward_seconds")]
+    pub skip_forward_seconds: u32,
+    /// How many feeds a sync requests at the same time.
+    #[serde(default = "default_feed_refresh_concurrency")]
+    pub feed_refresh_concurrency: usize,
+    /// How many episodes download at the same time.
+    #[serde(default = "default_download_concurrency")]
+    pub download_concurrency: usize,
+    #[serde(default = "default_feed_request_timeout_seconds")]
+    pub feed_request_timeout_seconds: u64,
+    #[serde(default = "default_user_agent")]
+    pub user_agent: String,
+    #[serde(default = "default_connect_timeout_seconds")]
+    pub connect_timeout_seconds: u64,
+    /// How long a download may go without receiving any data before it is abandoned.
+    #[serde(default = "default_read_timeout_seconds")]
+    pub read_timeout_seconds: u64,
+    /// An `http://`, `https://`, `socks5://` or `socks5h://` URL every request is sent through.
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Redirects followed before a request fails. Zero stops at the first redirect.
+    #[serde(default = "default_max_redirects")]
+    pub max_redirects: usize,
+}
+
+pub fn default_mark_played_threshold_seconds() -> u32 {
+    30
+}
+
+pub fn default_skip_back_seconds() -> u32 {
+    15
+}
+
+pub fn default_skip_forward_seconds() -> u32 {
+    30
+}
+
+pub fn default_feed_refresh_concurrency() -> usize {
+    8
+}
+
+pub fn default_download_concurrency() -> usize {
+    3
+}
+
+pub fn default_feed_request_timeout_seconds() -> u64 {
+    30
+}
+
+pub fn default_user_agent() -> String {
+    format!("castiron/{}", env!("CARGO_PKG_VERSION"))
+}
+
+pub fn default_connect_timeout_seconds() -> u64 {
+    10
+}
+
+pub fn default_read_timeout_seconds() -> u64 {
+    30
+}
+
+pub fn default_max_redirects() -> usize {
+    10
 }
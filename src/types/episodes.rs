@@ -1,4 +1,4 @@
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Episode {
     pub id: i32,
     pub guid: String,
@@ -10,4 +10,17 @@ pub struct Episode {
     pub url: String,
     pub feed_id: i32,
     pub downloaded: bool,
+    /// Show notes as the feed gave them, which is often HTML.
+    pub description: Option<String>,
+    pub duration_seconds: Option<u32>,
+    /// The enclosure's size as the feed gave it, before anything is downloaded.
+    pub size_bytes: Option<u64>,
+    pub season: Option<u32>,
+    pub episode_number: Option<u32>,
+    /// `full`, `trailer` or `bonus`.
+    pub episode_type: Option<String>,
+    /// The episode's web page.
+    pub link: Option<String>,
+    /// Artwork for this episode alone, when the feed gives it its own.
+    pub image_url: Option<String>,
 }
@@ -1,9 +1,89 @@
 use serde::{Deserialize, Serialize};
+use std::fmt;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct FeedMeta {
     pub id: i32,
     pub feed_url: String,
+    /// The name of the feed's cached document in the shows directory, once it has one.
     pub xml_file_path: Option<String>,
     pub feed_title: Option<String>,
+    /// The name of the feed's thumbnail in the thumbnails directory, once it has one.
     pub image_file_path: Option<String>,
+    /// The speed episodes of this feed start playing at, if one has been chosen.
+    pub playback_speed: Option<f32>,
+    /// `ETag` and `Last-Modified` from the last refresh that changed the feed.
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// Set for private feeds that need authenticating.
+    pub credentials: Option<FeedCredentials>,
+    /// What the feed says about the show, from its last refresh.
+    pub details: FeedDetails,
+}
+
+/// The show-level metadata a feed carries, shown above its episodes.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct FeedDetails {
+    pub author: Option<String>,
+    /// Often HTML.
+    pub description: Option<String>,
+    /// The show's web page.
+    pub website: Option<String>,
+    pub language: Option<String>,
+    /// Subcategories follow their parent, as in `Arts › Food`.
+    pub categories: Vec<String>,
+    pub explicit: Option<bool>,
+    /// `episodic` or `serial`.
+    pub show_type: Option<String>,
+    pub copyright: Option<String>,
+}
+
+/// What a private feed needs to authenticate, kept apart from its URL so the URL is safe to
+/// show and log. `Debug` leaves the secrets out.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct FeedCredentials {
+    /// Sent with HTTP Basic auth, along with `password`.
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Added to the request URL as `token_parameter=token`, for hosts that hand out secret
+    /// feed links.
+    pub token_parameter: Option<String>,
+    pub token: Option<String>,
+}
+
+impl FeedCredentials {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl fmt::Debug for FeedCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let redacted = |secret: &Option<String>| secret.as_ref().map(|_| "<redacted>");
+        f.debug_struct("FeedCredentials")
+            .field("username", &self.username)
+            .field("password", &redacted(&self.password))
+            .field("token_parameter", &self.token_parameter)
+            .field("token", &redacted(&self.token))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_redacts_secrets() {
+        let credentials = FeedCredentials {
+            username: Some(String::from("listener")),
+            password: Some(String::from("hunter2")),
+            token_parameter: Some(String::from("auth")),
+            token: Some(String::from("s3cret")),
+        };
+        let printed = format!("{credentials:?}");
+        assert!(printed.contains("listener"));
+        assert!(!printed.contains("hunter2"));
+        assert!(!printed.contains("s3cret"));
+    }
 }
@@ -1,56 +1,79 @@
+use std::{collections::HashMap, sync::Arc};
 use url::Url;
 
 use iced::{
     advanced::image::Handle,
     widget::{
-        button, column, container, row, text, text_input, vertical_space, Column, Rule, Scrollable,
+        button, checkbox, column, container, horizontal_space, row, text, text_input,
+        vertical_space, Column, Rule, Scrollable,
     },
     window, Alignment, Element, Length, Subscription, Task, Theme,
 };
 
 use super::widgets::{
-    config::Config,
+    config::{Config, NetworkSettingsDraft},
+    download_list::DownloadList,
     episode::Episode,
     episode_list::EpisodeList,
     feed::Feed,
+    feed_credentials::FeedCredentialsForm,
     feed_list::FeedList,
-    player::{Player, PlayerMessage},
+    player::{Player, PlayerMessage, POSITION_SAVE_INTERVAL_SECONDS},
+    show_header::ShowHeader,
 };
 use crate::{
     file_handling::{
-        config::{convert_theme_string_to_enum, create_config, load_or_create_config},
-        episodes::{
-            delete_episode_from_fs, get_episode_by_id, get_episode_list_database,
-            get_episodes_by_feed_id,
-        },
-        feeds::{
-            add_feed_to_database, delete_associated_episodes_and_xml, get_feed_by_id,
-            get_feed_list_database,
-        },
-        queue::{get_queue_database, save_queue},
+        config::{convert_theme_string_to_enum, create_config},
+        episodes::delete_episode_from_fs,
+        feeds::{delete_associated_episodes_and_xml, feed_thumbnail_file},
+        library::Library,
         setup::InitData,
     },
-    types::{config::CastironConfig, episodes::Episode as EpisodeData, feeds::FeedMeta},
+    networking::{
+        client::HttpClient,
+        download_manager::DownloadManager,
+        downloads::{discard_partial_download, DownloadFailure, DownloadProgress},
+        feeds::{FeedStatus, RefreshLimits, SyncResult},
+    },
+    types::{
+        config::{default_mark_played_threshold_seconds, CastironConfig},
+        episodes::Episode as EpisodeData,
+        feeds::{FeedCredentials, FeedMeta},
+    },
 };
 
 pub struct Castiron {
     app_view: AppView,
+    library: Option<Arc<dyn Library>>,
+    http: Option<HttpClient>,
+    feed_metas: Vec<FeedMeta>,
+    feed_statuses: HashMap<i32, FeedStatus>,
     feeds: FeedList,
     episodes: EpisodeList,
     episodes_for_show: EpisodeList,
     castiron_config: Option<Config>,
     feed_to_add: String,
+    network_settings: NetworkSettingsDraft,
+    credentials_to_set: FeedCredentials,
+    editing_credentials: bool,
     player: Player,
     queue: Vec<Episode>,
+    downloads: DownloadManager,
     theme: Theme,
+    hide_played: bool,
+    /// Set while asking whether every episode in the library should be marked played.
+    confirming_mark_all_played: bool,
+    init_error: Option<String>,
 }
 
+#[derive(Clone, Copy)]
 pub enum AppView {
     Feeds,
     Episodes,
     EpisodesForShow(i32),
     Config,
     Queue,
+    Downloads,
     Init,
 }
 
@@ -61,25 +84,66 @@ pub enum Message {
     ViewEpisodesForShow(i32),
     ViewQueue,
     ViewConfig,
+    ViewDownloads,
     AddFeed,
     UnfollowFeed(i32),
     SyncEpisodes,
+    OpenLink(String),
     DownloadEpisode(i32),
     PlayEpisode(i32),
     DeleteEpisode(i32),
+    MarkPlayed(i32, bool),
+    MarkFeedPlayed(i32, bool),
+    MarkAllPlayed(bool),
+    ConfirmMarkAllPlayed(bool),
+    HidePlayedToggled(bool),
     FeedsLoaded(Result<Vec<FeedMeta>, String>),
     EpisodesLoaded(Result<Option<Vec<EpisodeData>>, String>),
-    EpisodesSynced(Result<Option<Vec<EpisodeData>>, String>),
-    EpisodeDownloaded(Result<(), String>),
+    EpisodesSynced(Result<SyncResult, String>),
+    DownloadProgressed(i32, DownloadProgress),
+    EpisodeDownloaded(i32, Result<(), DownloadFailure>),
+    DownloadManagerMessage(DownloadManagerMessage),
+    CredentialsMessage(CredentialsMessage),
     FeedToAddUpdated(String),
     PlayerMessage(PlayerMessage),
     PodQueueMessage(PodQueueMessage),
     ThemeChanged(Theme),
+    MarkPlayedThresholdChanged(String),
+    SkipBackSecondsChanged(String),
+    SkipForwardSecondsChanged(String),
+    FeedRefreshConcurrencyChanged(String),
+    DownloadConcurrencyChanged(String),
+    FeedRequestTimeoutChanged(String),
+    UserAgentChanged(String),
+    ConnectTimeoutChanged(String),
+    ReadTimeoutChanged(String),
+    MaxRedirectsChanged(String),
+    ProxyChanged(String),
+    SetNetworkSettings,
     InitComplete(InitData),
-    InitFailed,
+    InitFailed(String),
     HandleClose,
 }
 
+#[derive(Debug, Clone)]
+pub enum DownloadManagerMessage {
+    Pause(i32),
+    Resume(i32),
+    Cancel(i32),
+    ClearFailed,
+}
+
+#[derive(Debug, Clone)]
+pub enum CredentialsMessage {
+    Toggle,
+    UsernameUpdated(String),
+    PasswordUpdated(String),
+    TokenParameterUpdated(String),
+    TokenUpdated(String),
+    Save(i32),
+    Clear(i32),
+}
+
 #[derive(Debug, Clone)]
 pub enum PodQueueMessage {
     RemoveFromQueue(i32),
@@ -91,72 +155,213 @@ impl Castiron {
     fn new() -> Self {
         Self {
             app_view: AppView::Init,
+            library: None,
+            http: None,
+            feed_metas: Vec::new(),
+            feed_statuses: HashMap::new(),
             feeds: FeedList::new(Vec::new()),
             episodes: EpisodeList::new(Vec::new()),
             episodes_for_show: EpisodeList::new(Vec::new()),
             castiron_config: None,
             feed_to_add: String::new(),
-            player: Player::new(None),
+            network_settings: NetworkSettingsDraft::default(),
+            credentials_to_set: FeedCredentials::default(),
+            editing_credentials: false,
+            player: Player::default(),
             queue: Vec::new(),
+            downloads: DownloadManager::default(),
             theme: Theme::default(),
+            hide_played: false,
+            confirming_mark_all_played: false,
+            init_error: None,
         }
     }
 
-    pub fn update_queue(&mut self) {
-        let new_queue: Vec<Episode> = self
-            .queue
+    fn feed_image_handle(&self, feed_id: i32) -> Option<Handle> {
+        self.feed_metas
+            .iter()
+            .find(|feed| feed.id == feed_id)
+            .and_then(feed_thumbnail_file)
+            .map(Handle::from_path)
+    }
+
+    fn episode_widget(&self, episode: &EpisodeData, viewing_from: AppView) -> Episode {
+        let mut widget = Episode::new(
+            episode,
+            viewing_from,
+            self.feed_image_handle(episode.feed_id),
+        );
+        widget.download_progress = self.downloads.progress(episode.id);
+        widget
+    }
+
+    fn refresh_download_progress(&mut self) {
+        for episode in self
+            .episodes
+            .episodes
+            .iter_mut()
+            .chain(self.episodes_for_show.episodes.iter_mut())
+            .chain(self.queue.iter_mut())
+        {
+            episode.download_progress = self.downloads.progress(episode.id);
+        }
+    }
+
+    fn feed_widgets(&self, feeds: &[FeedMeta]) -> Vec<Feed> {
+        feeds
+            .iter()
+            .map(|n| {
+                let mut feed = self.feed_widget(n);
+                feed.status = self.feed_statuses.get(&n.id).cloned();
+                feed
+            })
+            .collect()
+    }
+
+    fn feed_widget(&self, n: &FeedMeta) -> Feed {
+        match feed_thumbnail_file(n) {
+            Some(file_path) => match &n.feed_title {
+                Some(feed_title) => Feed::new(
+                    n.id,
+                    feed_title.to_owned(),
+                    Some(Handle::from_path(file_path)),
+                ),
+                None => Feed::new(
+                    n.id,
+                    n.feed_url.to_owned(),
+                    Some(Handle::from_path(file_path)),
+                ),
+            },
+            None => Feed::new(n.id, Default::default(), Default::default()),
+        }
+    }
+
+    /// Thumbnails come from the cached feed list, so episodes loaded before their feed need
+    /// their handles filled in once the feed arrives.
+    fn refresh_image_handles(&mut self) {
+        let handles: Vec<(i32, Option<Handle>)> = self
+            .feed_metas
             .iter()
-            .map(|episode| {
-                let updated_episode = get_episode_by_id(episode.id);
-                match updated_episode {
-                    Ok(u_episode) => {
-                        let handle = match get_feed_by_id(u_episode.feed_id) {
-                            Ok(feed) => match feed.image_file_path {
-                                Some(path) => Some(Handle::from_path(path)),
-                                None => None,
-                            },
-                            Err(_) => None,
-                        };
-                        Episode::new(
-                            u_episode.id,
-                            u_episode.feed_id,
-                            u_episode.guid,
-                            u_episode.title,
-                            u_episode.downloaded,
-                            AppView::Queue,
-                            handle,
-                        )
+            .map(|feed| (feed.id, self.feed_image_handle(feed.id)))
+            .collect();
+        for episode in self
+            .episodes
+            .episodes
+            .iter_mut()
+            .chain(self.episodes_for_show.episodes.iter_mut())
+            .chain(self.queue.iter_mut())
+        {
+            if let Some((_, handle)) = handles.iter().find(|(id, _)| *id == episode.feed_id) {
+                episode.image_handle = handle.to_owned();
+            }
+        }
+    }
+
+    /// Writes the player's position back to the library. Unless `force` is set, this only
+    /// happens once playback has moved `POSITION_SAVE_INTERVAL_SECONDS` from the last save.
+    /// Nothing is written after the position has been reset, until the listener seeks.
+    fn save_playback_position(&mut self, library: &dyn Library, force: bool) {
+        if self.player.position_reset {
+            return;
+        }
+        if let Some((id, seconds)) = self.player.position() {
+            if force
+                || (seconds - self.player.saved_seconds).abs() >= POSITION_SAVE_INTERVAL_SECONDS
+            {
+                match library.update_played_seconds(id, seconds) {
+                    Ok(_) => self.player.saved_seconds = seconds,
+                    Err(e) => eprintln!("Error saving playback position: {:?}", e),
+                }
+            }
+        }
+    }
+
+    /// Marks the playing episode played once it nears its end. Returns whether it was marked.
+    fn mark_played_if_finishing(&mut self, library: &dyn Library) -> bool {
+        let threshold = self
+            .castiron_config
+            .as_ref()
+            .map(|config| config.values.mark_played_threshold_seconds)
+            .unwrap_or_else(default_mark_played_threshold_seconds);
+        match self.player.id {
+            Some(id) if !self.player.played && self.player.is_finishing(threshold) => {
+                match library.update_episode_played_status(id, true) {
+                    Ok(_) => {
+                        self.player.played = true;
+                        self.player.reset_position();
+                        true
                     }
-                    Err(_) => {
-                        let handle = match get_feed_by_id(episode.feed_id) {
-                            Ok(feed) => match feed.image_file_path {
-                                Some(path) => Some(Handle::from_path(path)),
-                                None => None,
-                            },
-                            Err(_) => None,
-                        };
-                        Episode::new(
-                            episode.id,
-                            episode.feed_id,
-                            episode.guid.to_owned(),
-                            episode.title.to_owned(),
-                            episode.downloaded,
-                            AppView::Queue,
-                            handle,
-                        )
+                    Err(e) => {
+                        eprintln!("Error marking episode played: {:?}", e);
+                        false
                     }
                 }
+            }
+            _ => false,
+        }
+    }
+
+    /// Saves and applies new settings, unless the network settings in them are unusable.
+    /// Returns whether they were applied.
+    fn save_config(&mut self, values: CastironConfig) -> bool {
+        match HttpClient::new(&values) {
+            Ok(http) => self.http = Some(http),
+            Err(e) => {
+                eprintln!("Error applying network settings: {:?}", e);
+                return false;
+            }
+        }
+        if let Err(e) = create_config(Some(values.to_owned())) {
+            eprintln!("Error saving config: {:?}", e);
+        };
+        self.downloads
+            .set_max_concurrent(values.download_concurrency);
+        self.castiron_config = Some(Config::new(values, self.theme.clone()));
+        self.apply_skip_intervals();
+        true
+    }
+
+    fn apply_skip_intervals(&mut self) {
+        if let Some(config) = &self.castiron_config {
+            self.player.skip_back_seconds = config.values.skip_back_seconds;
+            self.player.skip_forward_seconds = config.values.skip_forward_seconds;
+        }
+    }
+
+    fn refresh_limits(&self) -> RefreshLimits {
+        self.castiron_config
+            .as_ref()
+            .map(|config| RefreshLimits::from(&config.values))
+            .unwrap_or_default()
+    }
+
+    /// Reloads the episode lists and queue after episodes change in the library.
+    fn reload_episodes(&mut self, library: Arc<dyn Library>) -> Task<Message> {
+        self.update_queue();
+        Task::perform(EpisodeList::load_episodes(library), Message::EpisodesLoaded)
+    }
+
+    pub fn update_queue(&mut self) {
+        let Some(library) = &self.library else {
+            return;
+        };
+        let new_queue: Vec<Episode> = self
+            .queue
+            .iter()
+            .map(|episode| match library.get_episode_by_id(episode.id) {
+                Ok(u_episode) => self.episode_widget(&u_episode, AppView::Queue),
+                Err(_) => episode.to_owned(),
             })
             .collect();
         self.queue = new_queue;
     }
-    pub fn view_queue(&self) -> Element<Message> {
+    pub fn view_queue(&self) -> Element<'_, Message> {
         let mut col_len: usize = 0;
         let column = self
             .queue
             .iter()
             .fold(Column::new().spacing(10), |col, content| {
-                col_len = col_len + 1;
+                col_len += 1;
                 col.push(
                     row![
                         content.view(),
@@ -197,112 +402,63 @@ impl Castiron {
     }
 
     pub fn update(&mut self, message: Message) -> Task<Message> {
-        match message {
-            // TOOD: implement state and UI for loading until init complete
-            Message::InitComplete(init_data) => {
-                self.feeds = FeedList::new(
-                    init_data
-                        .feeds
-                        .iter()
-                        .map(|n| match &n.image_file_path {
-                            Some(file_path) => match &n.feed_title {
-                                Some(feed_title) => Feed::new(
-                                    n.id,
-                                    feed_title.to_owned(),
-                                    Some(Handle::from_path(file_path.to_owned())),
-                                ),
-                                None => Feed::new(
-                                    n.id,
-                                    n.feed_url.to_owned(),
-                                    Some(Handle::from_path(file_path.to_owned())),
-                                ),
-                            },
-                            None => Feed::new(n.id, Default::default(), Default::default()),
-                        })
-                        .collect(),
-                );
-                self.episodes = EpisodeList::new(
-                    init_data
-                        .episodes
-                        .iter()
-                        .map(|n| {
-                            let handle = match get_feed_by_id(n.feed_id) {
-                                Ok(feed) => match feed.image_file_path {
-                                    Some(path) => Some(Handle::from_path(path)),
-                                    None => None,
-                                },
-                                Err(_) => None,
-                            };
-                            Episode::new(
-                                n.id,
-                                n.feed_id,
-                                n.guid.to_owned(),
-                                n.title.to_owned(),
-                                n.downloaded,
-                                AppView::Episodes,
-                                handle,
-                            )
-                        })
-                        .collect(),
-                );
-                self.castiron_config = Some(Config {
-                    values: init_data.config.to_owned(),
-                    theme: convert_theme_string_to_enum(init_data.config.to_owned().theme),
-                });
-                self.queue = init_data
-                    .queue
+        if let Message::InitComplete(init_data) = message {
+            self.feeds = FeedList::new(self.feed_widgets(&init_data.feeds));
+            self.feed_metas = init_data.feeds;
+            self.episodes = EpisodeList::new(
+                init_data
+                    .episodes
                     .iter()
-                    .map(|e| {
-                        let handle = match get_feed_by_id(e.feed_id) {
-                            Ok(feed) => match feed.image_file_path {
-                                Some(path) => Some(Handle::from_path(path)),
-                                None => None,
-                            },
-                            Err(_) => None,
-                        };
-                        Episode::new(
-                            e.id,
-                            e.feed_id,
-                            e.guid.to_owned(),
-                            e.title.to_owned(),
-                            e.downloaded,
-                            AppView::Queue,
-                            handle,
-                        )
-                    })
-                    .collect();
-                self.theme = convert_theme_string_to_enum(init_data.config.theme);
-                self.app_view = AppView::Feeds;
-                Task::none()
-            }
-            Message::InitFailed => Task::none(),
+                    .map(|n| self.episode_widget(n, AppView::Episodes))
+                    .collect(),
+            );
+            self.castiron_config = Some(Config {
+                values: init_data.config.to_owned(),
+                theme: convert_theme_string_to_enum(init_data.config.to_owned().theme),
+            });
+            self.queue = init_data
+                .queue
+                .iter()
+                .map(|e| self.episode_widget(e, AppView::Queue))
+                .collect();
+            self.apply_skip_intervals();
+            self.network_settings = NetworkSettingsDraft::new(&init_data.config);
+            self.downloads
+                .set_max_concurrent(init_data.config.download_concurrency);
+            self.theme = convert_theme_string_to_enum(init_data.config.theme);
+            self.library = Some(init_data.library);
+            self.http = Some(init_data.http);
+            self.app_view = AppView::Feeds;
+            return Task::none();
+        }
+        let (Some(library), Some(http)) = (self.library.clone(), self.http.clone()) else {
+            // TOOD: implement state and UI for loading until init complete
+            return match message {
+                Message::InitFailed(e) => {
+                    eprintln!("Initialization failed: {e}");
+                    self.init_error = Some(e);
+                    Task::none()
+                }
+                Message::HandleClose => window::get_latest().and_then(window::close),
+                _ => Task::none(),
+            };
+        };
+        match message {
+            Message::InitComplete(_) | Message::InitFailed(_) => Task::none(),
             Message::HandleClose => {
+                self.save_playback_position(library.as_ref(), true);
                 let ids = self.queue.iter().map(|n| n.id).collect();
-                save_queue(ids).unwrap();
+                if let Err(e) = library.save_queue(ids) {
+                    eprintln!("Error saving queue: {:?}", e);
+                }
                 window::get_latest().and_then(window::close)
             }
             Message::FeedsLoaded(feeds) => match feeds {
                 Err(_) => Task::none(),
                 Ok(data) => {
-                    let feed_list = data
-                        .iter()
-                        .map(|n| match &n.image_file_path {
-                            Some(file_path) => match &n.feed_title {
-                                Some(feed_title) => Feed::new(
-                                    n.id,
-                                    feed_title.to_owned(),
-                                    Some(Handle::from_path(file_path.to_owned())),
-                                ),
-                                None => Feed::new(
-                                    n.id,
-                                    n.feed_url.to_owned(),
-                                    Some(Handle::from_path(file_path.to_owned())),
-                                ),
-                            },
-                            None => Feed::new(n.id, Default::default(), Default::default()),
-                        })
-                        .collect();
-                    self.feeds = FeedList::new(feed_list);
+                    self.feeds = FeedList::new(self.feed_widgets(&data));
+                    self.feed_metas = data;
+                    self.refresh_image_handles();
                     Task::none()
                 }
             },
@@ -312,61 +468,22 @@ impl Castiron {
                     Task::none()
                 }
                 Ok(data) => {
-                    match data {
-                        Some(found) => {
-                            let episode_list = found
+                    if let Some(found) = data {
+                        self.episodes = EpisodeList::new(
+                            found
                                 .iter()
-                                .map(|n| {
-                                    let handle = match get_feed_by_id(n.feed_id) {
-                                        Ok(feed) => match feed.image_file_path {
-                                            Some(path) => Some(Handle::from_path(path)),
-                                            None => None,
-                                        },
-                                        Err(_) => None,
-                                    };
-                                    Episode::new(
-                                        n.id,
-                                        n.feed_id,
-                                        n.guid.to_owned(),
-                                        n.title.to_owned(),
-                                        n.downloaded,
-                                        AppView::Episodes,
-                                        handle,
-                                    )
-                                })
-                                .collect();
-                            self.episodes = EpisodeList::new(episode_list);
-                            match self.app_view {
-                                AppView::EpisodesForShow(id) => {
-                                    self.episodes_for_show = EpisodeList::new(
-                                        found
-                                            .iter()
-                                            .filter(|ep| ep.feed_id == id)
-                                            .map(|n| {
-                                                let handle = match get_feed_by_id(n.feed_id) {
-                                                    Ok(feed) => match feed.image_file_path {
-                                                        Some(path) => Some(Handle::from_path(path)),
-                                                        None => None,
-                                                    },
-                                                    Err(_) => None,
-                                                };
-                                                Episode::new(
-                                                    n.id,
-                                                    n.feed_id,
-                                                    n.guid.to_owned(),
-                                                    n.title.to_owned(),
-                                                    n.downloaded,
-                                                    AppView::EpisodesForShow(id),
-                                                    handle,
-                                                )
-                                            })
-                                            .collect(),
-                                    );
-                                }
-                                _ => {}
-                            }
+                                .map(|n| self.episode_widget(n, AppView::Episodes))
+                                .collect(),
+                        );
+                        if let AppView::EpisodesForShow(id) = self.app_view {
+                            self.episodes_for_show = EpisodeList::new(
+                                found
+                                    .iter()
+                                    .filter(|ep| ep.feed_id == id)
+                                    .map(|n| self.episode_widget(n, AppView::EpisodesForShow(id)))
+                                    .collect(),
+                            );
                         }
-                        None => {}
                     };
                     Task::none()
                 }
@@ -377,36 +494,19 @@ impl Castiron {
                     Task::none()
                 }
                 Ok(data) => {
-                    match data {
-                        Some(found) => {
-                            let episode_list = found
-                                .iter()
-                                .map(|n| {
-                                    let handle = match get_feed_by_id(n.feed_id) {
-                                        Ok(feed) => match feed.image_file_path {
-                                            Some(path) => Some(Handle::from_path(path)),
-                                            None => None,
-                                        },
-                                        Err(_) => None,
-                                    };
-                                    Episode::new(
-                                        n.id,
-                                        n.feed_id,
-                                        n.guid.to_owned(),
-                                        n.title.to_owned(),
-                                        n.downloaded,
-                                        AppView::Episodes,
-                                        handle,
-                                    )
-                                })
-                                .collect();
-                            self.episodes = EpisodeList::new(episode_list);
-                        }
-                        None => {}
-                    };
+                    self.episodes = EpisodeList::new(
+                        data.episodes
+                            .iter()
+                            .map(|n| self.episode_widget(n, AppView::Episodes))
+                            .collect(),
+                    );
+                    self.feed_statuses.extend(data.feed_statuses);
                     Task::batch([
-                        Task::perform(EpisodeList::load_episodes(), Message::EpisodesLoaded),
-                        Task::perform(FeedList::load_feeds(), Message::FeedsLoaded),
+                        Task::perform(
+                            EpisodeList::load_episodes(library.clone()),
+                            Message::EpisodesLoaded,
+                        ),
+                        Task::perform(FeedList::load_feeds(library), Message::FeedsLoaded),
                     ])
                 }
             },
@@ -419,33 +519,21 @@ impl Castiron {
                 Task::none()
             }
             Message::ViewEpisodesForShow(id) => {
-                let episodes_for_show_result = get_episodes_by_feed_id(id);
-                match episodes_for_show_result {
-                    Ok(episodes_for_show) => {
-                        let episode_list = episodes_for_show
-                            .iter()
-                            .map(|n| {
-                                let handle = match get_feed_by_id(n.feed_id) {
-                                    Ok(feed) => match feed.image_file_path {
-                                        Some(path) => Some(Handle::from_path(path)),
-                                        None => None,
-                                    },
-                                    Err(_) => None,
-                                };
-                                Episode::new(
-                                    n.id,
-                                    n.feed_id,
-                                    n.guid.to_owned(),
-                                    n.title.to_owned(),
-                                    n.downloaded,
-                                    AppView::EpisodesForShow(id),
-                                    handle,
-                                )
-                            })
-                            .collect();
-                        self.episodes_for_show = EpisodeList::new(episode_list);
+                self.editing_credentials = false;
+                if let Ok(feed) = library.get_feed_by_id(id) {
+                    self.credentials_to_set = feed.credentials.to_owned().unwrap_or_default();
+                    match self.feed_metas.iter_mut().find(|cached| cached.id == id) {
+                        Some(cached) => *cached = feed,
+                        None => self.feed_metas.push(feed),
                     }
-                    Err(_) => {}
+                }
+                if let Ok(episodes_for_show) = library.get_episodes_by_feed_id(id) {
+                    self.episodes_for_show = EpisodeList::new(
+                        episodes_for_show
+                            .iter()
+                            .map(|n| self.episode_widget(n, AppView::EpisodesForShow(id)))
+                            .collect(),
+                    );
                 }
                 self.app_view = AppView::EpisodesForShow(id);
                 Task::none()
@@ -458,19 +546,24 @@ impl Castiron {
                 self.app_view = AppView::Config;
                 Task::none()
             }
+            Message::ViewDownloads => {
+                self.app_view = AppView::Downloads;
+                Task::none()
+            }
             Message::AddFeed => {
-                if self.feed_to_add == String::new() {
-                    Task::none()
-                } else if let Err(_) = Url::parse(self.feed_to_add.as_str()) {
+                if self.feed_to_add == String::new()
+                    || Url::parse(self.feed_to_add.as_str()).is_err()
+                {
                     // TODO: warn user that URL is invalid
                     Task::none()
                 } else {
-                    let result = add_feed_to_database(self.feed_to_add.to_owned());
+                    let result = library.add_feed(self.feed_to_add.to_owned());
                     self.feed_to_add = String::new();
                     match result {
-                        Ok(_) => {
-                            Task::perform(EpisodeList::sync_episodes(), Message::EpisodesSynced)
-                        }
+                        Ok(_) => Task::perform(
+                            EpisodeList::sync_episodes(library, http, self.refresh_limits()),
+                            Message::EpisodesSynced,
+                        ),
                         Err(_) => Task::none(),
                     }
                 }
@@ -479,139 +572,359 @@ impl Castiron {
                 self.feed_to_add = val;
                 Task::none()
             }
-            Message::SyncEpisodes => {
-                Task::perform(EpisodeList::sync_episodes(), Message::EpisodesSynced)
-            }
-            Message::DownloadEpisode(guid) => Task::perform(
-                Episode::download_single_episode(guid),
-                Message::EpisodeDownloaded,
+            Message::SyncEpisodes => Task::perform(
+                EpisodeList::sync_episodes(library, http, self.refresh_limits()),
+                Message::EpisodesSynced,
             ),
-            Message::EpisodeDownloaded(result) => match result {
-                Ok(_) => {
-                    self.update_queue();
-                    Task::perform(EpisodeList::load_episodes(), Message::EpisodesLoaded)
+            Message::OpenLink(url) => {
+                if let Err(e) = open::that_detached(&url) {
+                    eprintln!("Error opening link: {:?}", e);
                 }
-                Err(e) => {
-                    eprintln!("Error downloading episode: {e}");
-                    Task::none()
+                Task::none()
+            }
+            Message::DownloadEpisode(id) => {
+                match library.get_episode_by_id(id) {
+                    Ok(episode) => self.downloads.enqueue(id, episode.title, episode.file_name),
+                    Err(e) => eprintln!("Error queueing download: {:?}", e),
                 }
-            },
+                self.refresh_download_progress();
+                Task::none()
+            }
+            Message::DownloadProgressed(id, progress) => {
+                self.downloads.update_progress(id, progress);
+                self.refresh_download_progress();
+                Task::none()
+            }
+            Message::EpisodeDownloaded(id, result) => {
+                if let Err(failure) = &result {
+                    eprintln!("Error downloading episode: {}", failure.message);
+                }
+                match self.downloads.finish(id, result) {
+                    true => {
+                        self.update_queue();
+                        Task::perform(EpisodeList::load_episodes(library), Message::EpisodesLoaded)
+                    }
+                    false => {
+                        self.refresh_download_progress();
+                        Task::none()
+                    }
+                }
+            }
+            Message::CredentialsMessage(message) => {
+                let draft = &mut self.credentials_to_set;
+                let non_empty = |value: String| Some(value).filter(|value| !value.is_empty());
+                let saved = match message {
+                    CredentialsMessage::Toggle => {
+                        self.editing_credentials = !self.editing_credentials;
+                        None
+                    }
+                    CredentialsMessage::UsernameUpdated(value) => {
+                        draft.username = non_empty(value);
+                        None
+                    }
+                    CredentialsMessage::PasswordUpdated(value) => {
+                        draft.password = non_empty(value);
+                        None
+                    }
+                    CredentialsMessage::TokenParameterUpdated(value) => {
+                        draft.token_parameter = non_empty(value);
+                        None
+                    }
+                    CredentialsMessage::TokenUpdated(value) => {
+                        draft.token = non_empty(value);
+                        None
+                    }
+                    CredentialsMessage::Save(feed_id) => Some((
+                        feed_id,
+                        Some(draft.to_owned()).filter(|credentials| !credentials.is_empty()),
+                    )),
+                    CredentialsMessage::Clear(feed_id) => {
+                        *draft = FeedCredentials::default();
+                        Some((feed_id, None))
+                    }
+                };
+                match saved {
+                    Some((feed_id, credentials)) => {
+                        match library.update_feed_credentials(feed_id, credentials) {
+                            Ok(_) => {
+                                self.editing_credentials = false;
+                                Task::batch([
+                                    Task::done(Message::ViewEpisodesForShow(feed_id)),
+                                    Task::perform(
+                                        EpisodeList::sync_episodes(
+                                            library,
+                                            http,
+                                            self.refresh_limits(),
+                                        ),
+                                        Message::EpisodesSynced,
+                                    ),
+                                ])
+                            }
+                            Err(e) => {
+                                eprintln!("Error saving feed credentials: {:?}", e);
+                                Task::none()
+                            }
+                        }
+                    }
+                    None => Task::none(),
+                }
+            }
+            Message::DownloadManagerMessage(message) => {
+                match message {
+                    DownloadManagerMessage::Pause(id) => self.downloads.pause(id),
+                    DownloadManagerMessage::Resume(id) => self.downloads.resume(id),
+                    DownloadManagerMessage::Cancel(id) => {
+                        if let Some(job) = self.downloads.cancel(id) {
+                            discard_partial_download(&job.file_name);
+                        }
+                    }
+                    DownloadManagerMessage::ClearFailed => self.downloads.clear_failed(),
+                }
+                self.refresh_download_progress();
+                Task::none()
+            }
             Message::PlayEpisode(id) => {
-                self.player = Player::new(Some(id));
+                self.save_playback_position(library.as_ref(), true);
+                self.player = Player::new(library.as_ref(), Some(id));
+                self.apply_skip_intervals();
                 // TODO: handle checking for episode in queue and, if found, removing it from queue
-                let found_idx = self.queue.iter().position(|episode| episode.id == id);
-                match found_idx {
-                    Some(idx) => {
-                        self.queue.remove(idx);
-                    }
-                    None => {}
+                if let Some(idx) = self.queue.iter().position(|episode| episode.id == id) {
+                    self.queue.remove(idx);
                 }
                 Task::none()
             }
             Message::PlayerMessage(message) => {
-                match message {
-                    PlayerMessage::Progress => match &self.player.sink {
-                        Some(sink) => match sink.empty() {
-                            true => {
-                                let episode_id = match self.queue.get(0) {
-                                    Some(episode) => Some(episode.id),
-                                    None => None,
-                                };
-                                match episode_id {
-                                    Some(id) => {
-                                        self.player = Player::new(Some(id));
-                                        self.queue.remove(0);
-                                    }
-                                    None => {}
-                                };
+                let marked_played = self.mark_played_if_finishing(library.as_ref());
+                if let PlayerMessage::Progress = message {
+                    if let Some(sink) = &self.player.sink {
+                        if sink.empty() {
+                            if let Some(id) = self.queue.first().map(|episode| episode.id) {
+                                self.save_playback_position(library.as_ref(), true);
+                                self.player = Player::new(library.as_ref(), Some(id));
+                                self.apply_skip_intervals();
+                                self.queue.remove(0);
                             }
-                            false => {}
-                        },
-                        None => {}
-                    },
-                    _ => {}
+                        }
+                    }
                 };
+                if let (PlayerMessage::SpeedChanged(speed), Some(feed_id)) =
+                    (&message, self.player.feed_id)
+                {
+                    if let Err(e) = library.update_feed_playback_speed(feed_id, speed.0) {
+                        eprintln!("Error saving playback speed: {:?}", e);
+                    }
+                }
+                let force_save = matches!(message, PlayerMessage::Pause);
                 self.player.update(message);
+                self.save_playback_position(library.as_ref(), force_save);
+                match marked_played {
+                    true => self.reload_episodes(library),
+                    false => Task::none(),
+                }
+            }
+            Message::MarkPlayed(id, played) => {
+                match library.update_episode_played_status(id, played) {
+                    Ok(_) => {
+                        if self.player.id == Some(id) {
+                            self.player.played = played;
+                            self.player.reset_position();
+                        }
+                        self.reload_episodes(library)
+                    }
+                    Err(e) => {
+                        eprintln!("Error marking episode: {:?}", e);
+                        Task::none()
+                    }
+                }
+            }
+            Message::MarkFeedPlayed(feed_id, played) => {
+                match library.update_feed_played_status(feed_id, played) {
+                    Ok(_) => {
+                        if self.player.feed_id == Some(feed_id) {
+                            self.player.played = played;
+                        }
+                        self.reload_episodes(library)
+                    }
+                    Err(e) => {
+                        eprintln!("Error marking episodes: {:?}", e);
+                        Task::none()
+                    }
+                }
+            }
+            Message::MarkAllPlayed(played) => {
+                self.confirming_mark_all_played = false;
+                match library.update_all_played_status(played) {
+                    Ok(_) => {
+                        if self.player.id.is_some() {
+                            self.player.played = played;
+                        }
+                        self.reload_episodes(library)
+                    }
+                    Err(e) => {
+                        eprintln!("Error marking episodes: {:?}", e);
+                        Task::none()
+                    }
+                }
+            }
+            Message::ConfirmMarkAllPlayed(confirming) => {
+                self.confirming_mark_all_played = confirming;
+                Task::none()
+            }
+            Message::HidePlayedToggled(hide_played) => {
+                self.hide_played = hide_played;
                 Task::none()
             }
-            Message::DeleteEpisode(guid) => match delete_episode_from_fs(guid) {
+            Message::DeleteEpisode(guid) => match delete_episode_from_fs(library.as_ref(), guid) {
                 Ok(_) => {
                     self.update_queue();
-                    Task::perform(EpisodeList::load_episodes(), Message::EpisodesLoaded)
+                    Task::perform(EpisodeList::load_episodes(library), Message::EpisodesLoaded)
                 }
                 Err(e) => {
                     eprintln!("Error deleting episode: {:?}", e);
                     Task::none()
                 }
             },
-            Message::UnfollowFeed(id) => match delete_associated_episodes_and_xml(id) {
-                Ok(_) => Task::batch([
-                    Task::perform(FeedList::load_feeds(), Message::FeedsLoaded),
-                    Task::perform(EpisodeList::load_episodes(), Message::EpisodesLoaded),
-                ]),
-                Err(e) => {
-                    eprintln!("Error deleting feed: {:?}", e);
-                    Task::none()
+            Message::UnfollowFeed(id) => {
+                match delete_associated_episodes_and_xml(library.as_ref(), id) {
+                    Ok(_) => Task::batch([
+                        Task::perform(FeedList::load_feeds(library.clone()), Message::FeedsLoaded),
+                        Task::perform(EpisodeList::load_episodes(library), Message::EpisodesLoaded),
+                    ]),
+                    Err(e) => {
+                        eprintln!("Error deleting feed: {:?}", e);
+                        Task::none()
+                    }
                 }
-            },
+            }
             Message::PodQueueMessage(pod_queue_message) => {
                 match pod_queue_message {
                     PodQueueMessage::RemoveFromQueue(id) => {
-                        let position = self.queue.iter().position(|pod| pod.id == id);
-                        match position {
-                            Some(index) => {
-                                self.queue.remove(index);
-                            }
-                            None => {}
+                        if let Some(index) = self.queue.iter().position(|pod| pod.id == id) {
+                            self.queue.remove(index);
                         }
                     }
                     PodQueueMessage::AddToQueue(id) => {
-                        let episode = get_episode_by_id(id);
-                        match episode {
-                            Ok(ep) => {
-                                let handle = match get_feed_by_id(ep.feed_id) {
-                                    Ok(feed) => match feed.image_file_path {
-                                        Some(path) => Some(Handle::from_path(path)),
-                                        None => None,
-                                    },
-                                    Err(_) => None,
-                                };
-                                self.queue.push(Episode::new(
-                                    ep.id,
-                                    ep.feed_id,
-                                    ep.guid,
-                                    ep.title,
-                                    ep.downloaded,
-                                    AppView::Queue,
-                                    handle,
-                                ))
-                            }
-                            Err(_) => (),
+                        if let Ok(ep) = library.get_episode_by_id(id) {
+                            self.queue.push(self.episode_widget(&ep, AppView::Queue))
                         }
                     }
                     PodQueueMessage::MoveToPosition(original_index, new_index) => {
-                        match new_index < self.queue.len() {
-                            true => self.queue.swap(original_index, new_index),
-                            false => (),
+                        if new_index < self.queue.len() {
+                            self.queue.swap(original_index, new_index)
                         }
                     }
                 }
                 Task::none()
             }
             Message::ThemeChanged(theme) => {
-                match create_config(Some(CastironConfig {
-                    theme: theme.to_string(),
-                })) {
-                    Ok(_) => (),
-                    Err(_) => (),
-                };
                 self.theme = theme;
-                match &self.castiron_config {
-                    Some(config) => {
-                        self.castiron_config =
-                            Some(Config::new(config.values.clone(), self.theme.clone()))
+                if let Some(config) = &self.castiron_config {
+                    self.save_config(CastironConfig {
+                        theme: self.theme.to_string(),
+                        ..config.values.to_owned()
+                    });
+                }
+                Task::none()
+            }
+            Message::MarkPlayedThresholdChanged(value) => {
+                if let (Some(seconds), Some(config)) =
+                    (parse_seconds(&value), &self.castiron_config)
+                {
+                    self.save_config(CastironConfig {
+                        mark_played_threshold_seconds: seconds,
+                        ..config.values.to_owned()
+                    });
+                }
+                Task::none()
+            }
+            Message::SkipBackSecondsChanged(value) => {
+                if let (Some(seconds), Some(config)) =
+                    (parse_seconds(&value), &self.castiron_config)
+                {
+                    self.save_config(CastironConfig {
+                        skip_back_seconds: seconds,
+                        ..config.values.to_owned()
+                    });
+                }
+                Task::none()
+            }
+            Message::SkipForwardSecondsChanged(value) => {
+                if let (Some(seconds), Some(config)) =
+                    (parse_seconds(&value), &self.castiron_config)
+                {
+                    self.save_config(CastironConfig {
+                        skip_forward_seconds: seconds,
+                        ..config.values.to_owned()
+                    });
+                }
+                Task::none()
+            }
+            Message::FeedRefreshConcurrencyChanged(value) => {
+                if let (Some(limit), Some(config)) = (
+                    value.trim().parse().ok().filter(|limit| *limit > 0),
+                    &self.castiron_config,
+                ) {
+                    self.save_config(CastironConfig {
+                        feed_refresh_concurrency: limit,
+                        ..config.values.to_owned()
+                    });
+                }
+                Task::none()
+            }
+            Message::DownloadConcurrencyChanged(value) => {
+                if let (Some(limit), Some(config)) = (
+                    value.trim().parse().ok().filter(|limit| *limit > 0),
+                    &self.castiron_config,
+                ) {
+                    self.save_config(CastironConfig {
+                        download_concurrency: limit,
+                        ..config.values.to_owned()
+                    });
+                }
+                Task::none()
+            }
+            Message::UserAgentChanged(value) => {
+                self.network_settings.user_agent = value;
+                Task::none()
+            }
+            Message::ConnectTimeoutChanged(value) => {
+                self.network_settings.connect_timeout_seconds = value;
+                Task::none()
+            }
+            Message::ReadTimeoutChanged(value) => {
+                self.network_settings.read_timeout_seconds = value;
+                Task::none()
+            }
+            Message::MaxRedirectsChanged(value) => {
+                self.network_settings.max_redirects = value;
+                Task::none()
+            }
+            Message::ProxyChanged(value) => {
+                self.network_settings.proxy = value;
+                Task::none()
+            }
+            Message::SetNetworkSettings => {
+                if let Some(config) = &self.castiron_config {
+                    let values = self.network_settings.apply(&config.values);
+                    // On success the fields show what is now in effect, including any that
+                    // were left unchanged. A proxy the client rejects stays in its field.
+                    if self.save_config(values.to_owned()) {
+                        self.network_settings = NetworkSettingsDraft::new(&values);
                     }
-                    None => (),
+                }
+                Task::none()
+            }
+            Message::FeedRequestTimeoutChanged(value) => {
+                // A zero timeout would fail every request, so it is ignored like any other
+                // invalid value.
+                if let (Some(seconds), Some(config)) = (
+                    parse_seconds(&value).filter(|seconds| *seconds > 0),
+                    &self.castiron_config,
+                ) {
+                    self.save_config(CastironConfig {
+                        feed_request_timeout_seconds: seconds.into(),
+                        ..config.values.to_owned()
+                    });
                 }
                 Task::none()
             }
@@ -619,30 +932,72 @@ impl Castiron {
     }
 
     pub fn subscription(&self) -> Subscription<Message> {
-        Subscription::batch(vec![
-            self.player.subscription(),
-            window::close_requests().map(|_| Message::HandleClose),
-        ])
+        let downloads = self
+            .library
+            .iter()
+            .zip(self.http.iter())
+            .flat_map(|(library, http)| {
+                self.downloads.running().into_iter().map(|(id, attempt)| {
+                    Episode::download(library.clone(), http.clone(), id, attempt)
+                })
+            });
+        Subscription::batch(
+            [
+                self.player.subscription(),
+                window::close_requests().map(|_| Message::HandleClose),
+            ]
+            .into_iter()
+            .chain(downloads),
+        )
     }
 
-    pub fn view(&self) -> Element<Message> {
+    pub fn view(&self) -> Element<'_, Message> {
         let main_content = match self.app_view {
             AppView::Feeds => self.feeds.view(),
-            AppView::Episodes => self.episodes.view(),
+            AppView::Episodes => column![
+                row![
+                    checkbox("Hide played", self.hide_played).on_toggle(Message::HidePlayedToggled),
+                    horizontal_space(),
+                ]
+                .push(match self.confirming_mark_all_played {
+                    true => row![
+                        text("Mark every episode in the library played?"),
+                        button("Mark All Played").on_press(Message::MarkAllPlayed(true)),
+                        button("Cancel").on_press(Message::ConfirmMarkAllPlayed(false)),
+                    ]
+                    .spacing(10)
+                    .align_y(Alignment::Center),
+                    false => row![button("Mark All Played")
+                        .on_press(Message::ConfirmMarkAllPlayed(true))],
+                })
+                .align_y(Alignment::Center)
+                .padding(10),
+                self.episodes.view(self.hide_played)
+            ]
+            .into(),
             AppView::EpisodesForShow(id) => {
-                let feed = get_feed_by_id(id);
-                match feed {
-                    Ok(f) => column![
-                        row![
-                            button("Back").on_press(Message::ViewFeeds),
-                            text(format!("{}", f.feed_title.unwrap_or(String::new())))
-                        ]
-                        .padding(10),
-                        self.episodes_for_show.view()
+                match self.feed_metas.iter().find(|feed| feed.id == id) {
+                    Some(f) => column![row![
+                        button("Back").on_press(Message::ViewFeeds),
+                        horizontal_space(),
+                        checkbox("Hide played", self.hide_played)
+                            .on_toggle(Message::HidePlayedToggled),
+                        button("Mark All Played").on_press(Message::MarkFeedPlayed(id, true)),
+                        button("Credentials")
+                            .on_press(Message::CredentialsMessage(CredentialsMessage::Toggle)),
                     ]
                     .spacing(10)
+                    .align_y(Alignment::Center)
+                    .padding(10)]
+                    .push_maybe(
+                        self.editing_credentials
+                            .then(|| FeedCredentialsForm::new(id, &self.credentials_to_set).view()),
+                    )
+                    .push(ShowHeader::new(f, self.feed_image_handle(id)).view())
+                    .push(self.episodes_for_show.view(self.hide_played))
+                    .spacing(10)
                     .into(),
-                    Err(_) => text("Error loading").into(),
+                    None => text("Error loading").into(),
                 }
             }
             AppView::Queue => match &self.queue.len() {
@@ -650,10 +1005,11 @@ impl Castiron {
                     .padding(20)
                     .center_x(Length::Fill)
                     .into(),
-                _ => self.view_queue().into(),
+                _ => self.view_queue(),
             },
+            AppView::Downloads => DownloadList::new(self.downloads.jobs()).view(),
             AppView::Config => match &self.castiron_config {
-                Some(config) => config.view().into(),
+                Some(config) => config.view(&self.network_settings),
                 None => container(text("Config does not exist."))
                     .padding(20)
                     .center_x(Length::Fill)
@@ -665,11 +1021,14 @@ impl Castiron {
                 .into(),
         };
         match self.app_view {
-            AppView::Init => container(text("Loading..."))
-                .padding(20)
-                .center_x(Length::Fill)
-                .center_y(Length::Fill)
-                .into(),
+            AppView::Init => container(match &self.init_error {
+                Some(e) => text(format!("Could not open your library: {e}")),
+                None => text("Loading..."),
+            })
+            .padding(20)
+            .center_x(Length::Fill)
+            .center_y(Length::Fill)
+            .into(),
             _ => column![
                 container(row![
                     container(
@@ -689,6 +1048,11 @@ impl Castiron {
                                 .padding(10)
                                 .width(Length::Fill),
                             Rule::horizontal(1),
+                            button(text("Downloads"))
+                                .on_press(Message::ViewDownloads)
+                                .padding(10)
+                                .width(Length::Fill),
+                            Rule::horizontal(1),
                             button(text("Config"))
                                 .on_press(Message::ViewConfig)
                                 .padding(10)
@@ -724,6 +1088,14 @@ impl Castiron {
     }
 }
 
+/// Reads a seconds value typed into a config field. Clearing the field counts as zero.
+fn parse_seconds(value: &str) -> Option<u32> {
+    match value.trim() {
+        "" => Some(0),
+        trimmed => trimmed.parse().ok(),
+    }
+}
+
 impl Default for Castiron {
     fn default() -> Self {
         Self::new()
@@ -1,9 +1,56 @@
 use crate::{types::config::CastironConfig, ui::gui::Message};
 use iced::{
-    widget::{container, horizontal_space, pick_list, row, text},
+    widget::{
+        button, column, container, horizontal_space, pick_list, row, scrollable, text, text_input,
+    },
     Alignment, Element, Length, Theme,
 };
 
+/// The network settings as they are being typed. Applying them rebuilds the HTTP client, so
+/// they only take effect once submitted rather than on every keystroke.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetworkSettingsDraft {
+    pub user_agent: String,
+    pub connect_timeout_seconds: String,
+    pub read_timeout_seconds: String,
+    pub max_redirects: String,
+    pub proxy: String,
+}
+
+impl NetworkSettingsDraft {
+    pub fn new(values: &CastironConfig) -> Self {
+        Self {
+            user_agent: values.user_agent.to_owned(),
+            connect_timeout_seconds: values.connect_timeout_seconds.to_string(),
+            read_timeout_seconds: values.read_timeout_seconds.to_string(),
+            max_redirects: values.max_redirects.to_string(),
+            proxy: values.proxy.to_owned().unwrap_or_default(),
+        }
+    }
+
+    /// `values` with the draft applied. Fields that do not hold a valid value, including
+    /// timeouts of zero which would fail every request, keep their current setting.
+    pub fn apply(&self, values: &CastironConfig) -> CastironConfig {
+        let seconds = |draft: &str| draft.trim().parse().ok().filter(|seconds| *seconds > 0);
+        CastironConfig {
+            user_agent: Some(self.user_agent.trim().to_string())
+                .filter(|user_agent| !user_agent.is_empty())
+                .unwrap_or_else(|| values.user_agent.to_owned()),
+            connect_timeout_seconds: seconds(&self.connect_timeout_seconds)
+                .unwrap_or(values.connect_timeout_seconds),
+            read_timeout_seconds: seconds(&self.read_timeout_seconds)
+                .unwrap_or(values.read_timeout_seconds),
+            max_redirects: self
+                .max_redirects
+                .trim()
+                .parse()
+                .unwrap_or(values.max_redirects),
+            proxy: Some(self.proxy.trim().to_string()).filter(|proxy| !proxy.is_empty()),
+            ..values.to_owned()
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Config {
     pub values: CastironConfig,
@@ -15,18 +62,185 @@ impl Config {
         Self { values, theme }
     }
 
-    pub fn view(&self) -> Element<Message> {
-        container(
-            row![
-                text("Theme"),
-                horizontal_space(),
-                pick_list(Theme::ALL, Some(&self.theme), Message::ThemeChanged)
-            ]
-            .width(300)
-            .padding(20)
-            .align_y(Alignment::Center),
+    /// `network` holds the network settings being typed in, shown in place of the saved ones.
+    pub fn view<'a>(&'a self, network: &'a NetworkSettingsDraft) -> Element<'a, Message> {
+        scrollable(
+            container(column![
+                row![
+                    text("Theme"),
+                    horizontal_space(),
+                    pick_list(Theme::ALL, Some(&self.theme), Message::ThemeChanged)
+                ]
+                .width(300)
+                .padding(20)
+                .align_y(Alignment::Center),
+                row![
+                    text("Mark played with seconds left"),
+                    horizontal_space(),
+                    text_input(
+                        "seconds",
+                        &self.values.mark_played_threshold_seconds.to_string()
+                    )
+                    .on_input(Message::MarkPlayedThresholdChanged)
+                    .width(60)
+                ]
+                .width(300)
+                .padding(20)
+                .align_y(Alignment::Center),
+                row![
+                    text("Skip back seconds"),
+                    horizontal_space(),
+                    text_input("seconds", &self.values.skip_back_seconds.to_string())
+                        .on_input(Message::SkipBackSecondsChanged)
+                        .width(60)
+                ]
+                .width(300)
+                .padding(20)
+                .align_y(Alignment::Center),
+                row![
+                    text("Skip forward seconds"),
+                    horizontal_space(),
+                    text_input("seconds", &self.values.skip_forward_seconds.to_string())
+                        .on_input(Message::SkipForwardSecondsChanged)
+                        .width(60)
+                ]
+                .width(300)
+                .padding(20)
+                .align_y(Alignment::Center),
+                row![
+                    text("Feeds to refresh at once"),
+                    horizontal_space(),
+                    text_input("feeds", &self.values.feed_refresh_concurrency.to_string())
+                        .on_input(Message::FeedRefreshConcurrencyChanged)
+                        .width(60)
+                ]
+                .width(300)
+                .padding(20)
+                .align_y(Alignment::Center),
+                row![
+                    text("Episodes to download at once"),
+                    horizontal_space(),
+                    text_input("episodes", &self.values.download_concurrency.to_string())
+                        .on_input(Message::DownloadConcurrencyChanged)
+                        .width(60)
+                ]
+                .width(300)
+                .padding(20)
+                .align_y(Alignment::Center),
+                row![
+                    text("Feed request timeout seconds"),
+                    horizontal_space(),
+                    text_input(
+                        "seconds",
+                        &self.values.feed_request_timeout_seconds.to_string()
+                    )
+                    .on_input(Message::FeedRequestTimeoutChanged)
+                    .width(60)
+                ]
+                .width(300)
+                .padding(20)
+                .align_y(Alignment::Center),
+                row![
+                    text("User agent"),
+                    horizontal_space(),
+                    text_input("user agent", &network.user_agent)
+                        .on_input(Message::UserAgentChanged)
+                        .on_submit(Message::SetNetworkSettings)
+                        .width(200),
+                    button("Set").on_press(Message::SetNetworkSettings)
+                ]
+                .width(400)
+                .padding(20)
+                .spacing(10)
+                .align_y(Alignment::Center),
+                row![
+                    text("Connect timeout seconds"),
+                    horizontal_space(),
+                    text_input("seconds", &network.connect_timeout_seconds)
+                        .on_input(Message::ConnectTimeoutChanged)
+                        .on_submit(Message::SetNetworkSettings)
+                        .width(60),
+                    button("Set").on_press(Message::SetNetworkSettings)
+                ]
+                .width(400)
+                .padding(20)
+                .spacing(10)
+                .align_y(Alignment::Center),
+                row![
+                    text("Read timeout seconds"),
+                    horizontal_space(),
+                    text_input("seconds", &network.read_timeout_seconds)
+                        .on_input(Message::ReadTimeoutChanged)
+                        .on_submit(Message::SetNetworkSettings)
+                        .width(60),
+                    button("Set").on_press(Message::SetNetworkSettings)
+                ]
+                .width(400)
+                .padding(20)
+                .spacing(10)
+                .align_y(Alignment::Center),
+                row![
+                    text("Redirects to follow"),
+                    horizontal_space(),
+                    text_input("redirects", &network.max_redirects)
+                        .on_input(Message::MaxRedirectsChanged)
+                        .on_submit(Message::SetNetworkSettings)
+                        .width(60),
+                    button("Set").on_press(Message::SetNetworkSettings)
+                ]
+                .width(400)
+                .padding(20)
+                .spacing(10)
+                .align_y(Alignment::Center),
+                row![
+                    text("Proxy"),
+                    horizontal_space(),
+                    text_input("socks5h://127.0.0.1:9050", &network.proxy)
+                        .on_input(Message::ProxyChanged)
+                        .on_submit(Message::SetNetworkSettings)
+                        .width(200),
+                    button("Set").on_press(Message::SetNetworkSettings)
+                ]
+                .width(400)
+                .padding(20)
+                .spacing(10)
+                .align_y(Alignment::Center),
+            ])
+            .center_x(Length::Fill),
         )
-        .center_x(Length::Fill)
         .into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_handling::config::default_config;
+
+    #[test]
+    fn test_network_settings_draft_keeps_invalid_fields() {
+        let values = default_config();
+        let draft = NetworkSettingsDraft {
+            user_agent: String::from(" podcatcher/2 "),
+            connect_timeout_seconds: String::from("0"),
+            read_timeout_seconds: String::from("45"),
+            max_redirects: String::from("lots"),
+            proxy: String::from("  "),
+        };
+        let applied = draft.apply(&values);
+        assert_eq!(applied.user_agent, "podcatcher/2");
+        assert_eq!(
+            applied.connect_timeout_seconds,
+            values.connect_timeout_seconds
+        );
+        assert_eq!(applied.read_timeout_seconds, 45);
+        assert_eq!(applied.max_redirects, values.max_redirects);
+        assert_eq!(applied.proxy, None);
+        assert_eq!(
+            NetworkSettingsDraft::new(&applied)
+                .apply(&applied)
+                .user_agent,
+            "podcatcher/2"
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{
+    networking::download_manager::{DownloadJob, JobState},
+    ui::gui::{DownloadManagerMessage, Message},
+};
+use iced::{
+    widget::{
+        button, column, container, horizontal_space, progress_bar, row, text, Column, Row,
+        Scrollable,
+    },
+    Alignment, Element, Length, Renderer, Theme,
+};
+
+pub struct DownloadList<'a> {
+    jobs: &'a [DownloadJob],
+}
+
+impl<'a> DownloadList<'a> {
+    pub fn new(jobs: &'a [DownloadJob]) -> Self {
+        Self { jobs }
+    }
+
+    fn job_view(job: &DownloadJob) -> Element<'_, Message> {
+        let action = |label: &'static str, message: DownloadManagerMessage| {
+            button(text(label)).on_press(Message::DownloadManagerMessage(message))
+        };
+        let (status, actions): (Element<'_, Message>, Row<Message, Theme, Renderer>) =
+            match &job.state {
+                JobState::Queued => (
+                    text("Queued").into(),
+                    row![
+                        action("Pause", DownloadManagerMessage::Pause(job.episode_id)),
+                        action("Cancel", DownloadManagerMessage::Cancel(job.episode_id)),
+                    ],
+                ),
+                JobState::Running { attempt, progress } => (
+                    column![
+                        progress_bar(0.0..=1.0, progress.fraction().unwrap_or_default()).height(10),
+                        text(match attempt {
+                            0 => format!("{} MB", progress.downloaded / 1_000_000),
+                            _ => format!("{} MB, retry {attempt}", progress.downloaded / 1_000_000),
+                        }),
+                    ]
+                    .into(),
+                    row![
+                        action("Pause", DownloadManagerMessage::Pause(job.episode_id)),
+                        action("Cancel", DownloadManagerMessage::Cancel(job.episode_id)),
+                    ],
+                ),
+                JobState::Paused => (
+                    text("Paused").into(),
+                    row![
+                        action("Resume", DownloadManagerMessage::Resume(job.episode_id)),
+                        action("Cancel", DownloadManagerMessage::Cancel(job.episode_id)),
+                    ],
+                ),
+                JobState::Failed(reason) => (
+                    text(format!("Failed: {reason}")).into(),
+                    row![
+                        action("Retry", DownloadManagerMessage::Resume(job.episode_id)),
+                        action("Remove", DownloadManagerMessage::Cancel(job.episode_id)),
+                    ],
+                ),
+            };
+        container(
+            row![
+                text(job.title.to_owned()).width(300),
+                container(status).width(Length::Fill),
+                actions.spacing(10),
+            ]
+            .spacing(20)
+            .align_y(Alignment::Center),
+        )
+        .padding(20)
+        .into()
+    }
+
+    pub fn view(&self) -> Element<'a, Message> {
+        match self.jobs.len() {
+            0 => container(text("No downloads in progress."))
+                .padding(20)
+                .center_x(Length::Fill)
+                .into(),
+            _ => column![
+                row![
+                    horizontal_space(),
+                    button(text("Clear Failed")).on_press(Message::DownloadManagerMessage(
+                        DownloadManagerMessage::ClearFailed
+                    )),
+                ]
+                .padding(10),
+                Scrollable::new(
+                    self.jobs
+                        .iter()
+                        .fold(Column::new().spacing(10), |col, job| {
+                            col.push(Self::job_view(job))
+                        }),
+                )
+                .width(Length::Fill)
+                .height(Length::Fill),
+            ]
+            .into(),
+        }
+    }
+}
@@ -1,43 +1,77 @@
-use crate::networking::downloads::download_episode_by_guid;
-use crate::ui::gui::{AppView, Message, PodQueueMessage};
+use crate::file_handling::library::Library;
+use crate::networking::{
+    client::HttpClient,
+    download_manager::retry_delay,
+    downloads::{
+        download_episode_by_guid, episode_thumbnail_file, DownloadFailure, DownloadProgress,
+    },
+};
+use crate::types::episodes::Episode as EpisodeData;
+use crate::ui::{
+    gui::{AppView, Message, PodQueueMessage},
+    widgets::player::format_timestamp,
+};
+use std::sync::Arc;
+
 use iced::{
     advanced::image::Handle,
-    widget::{button, container, horizontal_space, image, row, text, Row},
-    Element, Length, Renderer, Theme,
+    futures::SinkExt,
+    stream,
+    widget::{button, column, container, horizontal_space, image, progress_bar, row, text, Row},
+    Element, Length, Renderer, Subscription, Theme,
 };
 
-// TODO: implement download progress indicator
+/// How much of an episode's show notes is shown under its title.
+const SUMMARY_CHARS: usize = 160;
+
+#[derive(Clone)]
 pub struct Episode {
     pub id: i32,
     pub feed_id: i32,
-    pub guid: String,
     pub title: String,
+    /// Season and episode number, type, duration and size, whichever the feed gave.
+    pub details: String,
+    /// The start of the show notes, as plain text.
+    pub summary: Option<String>,
+    /// The episode's web page.
+    pub link: Option<String>,
     pub downloaded: bool,
+    pub played: bool,
     pub viewing_from: AppView,
+    /// The episode's own artwork, once it has been downloaded.
+    pub artwork: Option<Handle>,
+    /// The show's thumbnail, shown when the episode has no artwork of its own.
     pub image_handle: Option<Handle>,
+    pub download_progress: Option<DownloadProgress>,
 }
 
 impl Episode {
-    pub fn new(
-        id: i32,
-        feed_id: i32,
-        guid: String,
-        title: String,
-        downloaded: bool,
-        viewing_from: AppView,
-        image_handle: Option<Handle>,
-    ) -> Self {
+    pub fn new(episode: &EpisodeData, viewing_from: AppView, image_handle: Option<Handle>) -> Self {
         Self {
-            id,
-            feed_id,
-            guid,
-            title,
-            downloaded,
+            id: episode.id,
+            feed_id: episode.feed_id,
+            title: episode.title.to_owned(),
+            details: episode_details(episode),
+            summary: episode
+                .description
+                .as_deref()
+                .map(|description| plain_text(description, SUMMARY_CHARS))
+                .filter(|summary| !summary.is_empty()),
+            link: episode.link.to_owned(),
+            downloaded: episode.downloaded,
+            played: episode.played,
             viewing_from,
+            artwork: episode
+                .image_url
+                .as_deref()
+                .map(|url| episode_thumbnail_file(episode.feed_id, url))
+                .filter(|path| path.is_file())
+                .map(Handle::from_path),
             image_handle,
+            download_progress: None,
         }
     }
-    pub fn view(&self) -> Element<Message> {
+    pub fn view(&self) -> Element<'_, Message> {
         let action_container: Row<Message, Theme, Renderer> = match self.downloaded {
             true => match self.viewing_from {
                 AppView::Queue => {
@@ -58,35 +92,193 @@ impl Episode {
                         .width(Length::FillPortion(3))
                 ),
             },
-            false => row!(button(text("Download")).on_press(Message::DownloadEpisode(self.id)),),
+            false => match self.download_progress {
+                Some(progress) => row!(progress_bar(
+                    0.0..=1.0,
+                    progress.fraction().unwrap_or_default()
+                )
+                .width(Length::FillPortion(6))
+                .height(10)),
+                None => row!(button(text("Download")).on_press(Message::DownloadEpisode(self.id)),),
+            },
+        };
+        let action_container = match self.viewing_from {
+            AppView::Queue => action_container,
+            _ => action_container.push(
+                button(text(match self.played {
+                    true => "Unplayed",
+                    false => "Played",
+                }))
+                .on_press(Message::MarkPlayed(self.id, !self.played))
+                .width(Length::FillPortion(3)),
+            ),
         };
-        match &self.image_handle {
-            Some(handle) => container(row!(
-                image(handle).height(100),
-                text(self.title.to_owned()).width(300),
-                action_container
-            ))
-            .width(Length::Shrink)
-            .max_width(600)
-            .padding(20)
-            .center_y(Length::Shrink)
-            .into(),
-            None => container(row!(
-                text(self.title.to_owned()).width(300),
-                action_container
-            ))
-            .width(Length::Shrink)
-            .max_width(600)
-            .padding(20)
-            .center_y(Length::Shrink)
-            .into(),
+        let title = match self.played {
+            true => format!("{} (played)", self.title),
+            false => self.title.to_owned(),
+        };
+        let about = column![text(title)]
+            .push_maybe((!self.details.is_empty()).then(|| text(self.details.to_owned()).size(12)))
+            .push_maybe(
+                self.summary
+                    .to_owned()
+                    .map(|summary| text(summary).size(12)),
+            )
+            .push_maybe(self.link.to_owned().map(|link| {
+                button(text("Episode page").size(12))
+                    .style(button::text)
+                    .padding(0)
+                    .on_press(Message::OpenLink(link))
+            }))
+            .spacing(4)
+            .width(300);
+        match self.artwork.as_ref().or(self.image_handle.as_ref()) {
+            Some(handle) => container(row!(image(handle).height(100), about, action_container))
+                .width(Length::Shrink)
+                .max_width(600)
+                .padding(20)
+                .center_y(Length::Shrink)
+                .into(),
+            None => container(row!(about, action_container))
+                .width(Length::Shrink)
+                .max_width(600)
+                .padding(20)
+                .center_y(Length::Shrink)
+                .into(),
         }
     }
 
-    pub async fn download_single_episode(id: i32) -> Result<(), String> {
-        match download_episode_by_guid(id).await {
-            Ok(_) => Ok(()),
-            Err(e) => Err(String::from(format!("Error downloading episode: {:?}", e))),
+    /// Makes one attempt at downloading an episode for as long as the subscription is kept,
+    /// reporting progress as it goes and finishing with `Message::EpisodeDownloaded`. Retries
+    /// wait out their backoff first.
+    pub fn download(
+        library: Arc<dyn Library>,
+        http: HttpClient,
+        id: i32,
+        attempt: u32,
+    ) -> Subscription<Message> {
+        Subscription::run_with_id(
+            ("download", id, attempt),
+            stream::channel(100, move |mut output| async move {
+                tokio::time::sleep(retry_delay(attempt)).await;
+                let result = download_episode_by_guid(library.as_ref(), &http, id, |progress| {
+                    // Progress is best effort; a full channel just skips an update.
+                    let _ = output.try_send(Message::DownloadProgressed(id, progress));
+                })
+                .await;
+                let result = result.map(|_| ()).map_err(DownloadFailure::from);
+                let _ = output.send(Message::EpisodeDownloaded(id, result)).await;
+            }),
+        )
+    }
+}
+
+/// What the feed says about an episode besides its title, such as `S2 E14 · Bonus · 1:02:03 · 38 MB`.
+fn episode_details(episode: &EpisodeData) -> String {
+    let numbering = match (episode.season, episode.episode_number) {
+        (Some(season), Some(number)) => Some(format!("S{season} E{number}")),
+        (Some(season), None) => Some(format!("Season {season}")),
+        (None, Some(number)) => Some(format!("Episode {number}")),
+        (None, None) => None,
+    };
+    let kind = match episode.episode_type.as_deref() {
+        Some("trailer") => Some(String::from("Trailer")),
+        Some("bonus") => Some(String::from("Bonus")),
+        _ => None,
+    };
+    [
+        numbering,
+        kind,
+        episode
+            .duration_seconds
+            .map(|seconds| format_timestamp(seconds as f32)),
+        episode.size_bytes.map(format_size),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(" · ")
+}
+
+/// A size in decimal units, the way feeds and file managers usually show them.
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..=999_999 => format!("{} KB", bytes.div_ceil(1000)),
+        1_000_000..=999_999_999 => format!("{} MB", bytes / 1_000_000),
+        _ => format!("{:.1} GB", bytes as f64 / 1e9),
+    }
+}
+
+/// Show notes with their HTML tags dropped, the common entities decoded and whitespace
+/// collapsed, cut to `max_chars` with an ellipsis.
+pub fn plain_text(html: &str, max_chars: usize) -> String {
+    let mut stripped = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                stripped.push(' ');
+            }
+            _ if !in_tag => stripped.push(c),
+            _ => (),
         }
     }
+    let decoded = [
+        ("&nbsp;", " "),
+        ("&lt;", "<"),
+        ("&gt;", ">"),
+        ("&quot;", "\""),
+        ("&#39;", "'"),
+        ("&apos;", "'"),
+        ("&amp;", "&"),
+    ]
+    .iter()
+    .fold(stripped, |text, (entity, replacement)| {
+        text.replace(entity, replacement)
+    });
+    let collapsed = decoded.split_whitespace().collect::<Vec<_>>().join(" ");
+    match collapsed.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", collapsed[..end].trim_end()),
+        None => collapsed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_episode_details() {
+        let episode = EpisodeData {
+            season: Some(2),
+            episode_number: Some(14),
+            episode_type: Some(String::from("bonus")),
+            duration_seconds: Some(3723),
+            size_bytes: Some(38_400_000),
+            ..EpisodeData::default()
+        };
+        assert_eq!(
+            episode_details(&episode),
+            "S2 E14 · Bonus · 1:02:03 · 38 MB"
+        );
+        let sparse = EpisodeData {
+            episode_type: Some(String::from("full")),
+            duration_seconds: Some(2712),
+            ..EpisodeData::default()
+        };
+        assert_eq!(episode_details(&sparse), "45:12");
+        assert_eq!(episode_details(&EpisodeData::default()), "");
+    }
+
+    #[test]
+    fn test_plain_text() {
+        assert_eq!(
+            plain_text("<p>Fish &amp; chips</p>\n<p>with   <b>vinegar</b></p>", 100),
+            "Fish & chips with vinegar"
+        );
+        assert_eq!(plain_text("one two three", 7), "one two…");
+        assert_eq!(plain_text("ünïcødé", 3), "ünï…");
+    }
 }
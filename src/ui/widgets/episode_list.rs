@@ -1,8 +1,15 @@
 use super::episode::Episode;
 use crate::{
-    file_handling::episodes::get_episode_list_database, networking::feeds::sync_episode_list,
-    types::episodes::Episode as EpisodeData, ui::gui::Message,
+    file_handling::library::Library,
+    networking::{
+        client::HttpClient,
+        feeds::{sync_episode_list, RefreshLimits, SyncResult},
+    },
+    types::episodes::Episode as EpisodeData,
+    ui::gui::Message,
 };
+use std::sync::Arc;
+
 use iced::{
     widget::{container, text, Column, Scrollable},
     Element, Length,
@@ -16,7 +23,7 @@ impl EpisodeList {
     pub fn new(episodes: Vec<Episode>) -> Self {
         Self { episodes }
     }
-    pub fn view(&self) -> Element<Message> {
+    pub fn view(&self, hide_played: bool) -> Element<'_, Message> {
         match self.episodes.len() {
             0 => container(text("No episodes from feeds you follow."))
                 .padding(20)
@@ -25,6 +32,7 @@ impl EpisodeList {
             _ => Scrollable::new(
                 self.episodes
                     .iter()
+                    .filter(|episode| !(hide_played && episode.played))
                     .fold(Column::new().spacing(10), |col, content| {
                         col.push(content.view())
                     }),
@@ -34,24 +42,24 @@ impl EpisodeList {
         }
     }
 
-    pub async fn load_episodes() -> Result<Option<Vec<EpisodeData>>, String> {
-        match get_episode_list_database() {
+    pub async fn load_episodes(
+        library: Arc<dyn Library>,
+    ) -> Result<Option<Vec<EpisodeData>>, String> {
+        match library.get_episodes() {
             Ok(data) => Ok(Some(data)),
-            Err(e) => Err(String::from(format!(
-                "Error fetching episodes from database: {:?}",
-                e
-            ))),
+            Err(e) => Err(format!("Error fetching episodes from database: {:?}", e)),
         }
     }
 
-    pub async fn sync_episodes() -> Result<Option<Vec<EpisodeData>>, String> {
-        let result = sync_episode_list().await;
+    pub async fn sync_episodes(
+        library: Arc<dyn Library>,
+        http: HttpClient,
+        limits: RefreshLimits,
+    ) -> Result<SyncResult, String> {
+        let result = sync_episode_list(library.as_ref(), &http, limits).await;
         match result {
-            Ok(res) => match res {
-                Some(val) => Ok(Some(val)),
-                None => Ok(None),
-            },
-            Err(e) => Err(String::from(format!("Error syncing episodes: {:?}", e))),
+            Ok(res) => Ok(res),
+            Err(e) => Err(format!("Error syncing episodes: {:?}", e)),
         }
     }
 }
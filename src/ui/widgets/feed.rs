@@ -1,13 +1,14 @@
-use crate::ui::gui::Message;
+use crate::{networking::feeds::FeedStatus, ui::gui::Message};
 use iced::{
     advanced::image::Handle,
-    widget::{button, container, image, row, text},
+    widget::{button, column, container, image, row, text},
     Element, Length,
 };
 pub struct Feed {
     id: i32,
     feed_title: String,
     image_handle: Option<Handle>,
+    pub status: Option<FeedStatus>,
 }
 
 impl Feed {
@@ -16,16 +17,27 @@ impl Feed {
             id,
             feed_title,
             image_handle,
+            status: None,
         }
     }
-    pub fn view(&self) -> Element<Message> {
+    pub fn view(&self) -> Element<'_, Message> {
         let image = match &self.image_handle {
             Some(handle) => image(handle),
             None => image(""),
         };
         container(row!(
             image.height(50),
-            text(self.feed_title.to_owned()).width(Length::FillPortion(6)),
+            column![
+                text(self.feed_title.to_owned()),
+                text(match &self.status {
+                    None => String::new(),
+                    Some(FeedStatus::Unchanged) => String::from("Unchanged"),
+                    Some(FeedStatus::Updated) => String::from("Updated"),
+                    Some(FeedStatus::Failed(e)) => format!("Failed: {e}"),
+                })
+                .size(12),
+            ]
+            .width(Length::FillPortion(6)),
             button(text("Unfollow"))
                 .on_press(Message::UnfollowFeed(self.id))
                 .width(Length::FillPortion(3)),
@@ -0,0 +1,71 @@
+use crate::{
+    types::feeds::FeedCredentials,
+    ui::gui::{CredentialsMessage, Message},
+};
+use iced::{
+    widget::{button, column, row, text, text_input},
+    Alignment, Element,
+};
+
+/// Editing the credentials of a private feed. The password and token fields are masked.
+pub struct FeedCredentialsForm<'a> {
+    feed_id: i32,
+    draft: &'a FeedCredentials,
+}
+
+impl<'a> FeedCredentialsForm<'a> {
+    pub fn new(feed_id: i32, draft: &'a FeedCredentials) -> Self {
+        Self { feed_id, draft }
+    }
+
+    pub fn view(&self) -> Element<'a, Message> {
+        let field = |placeholder: &str,
+                     value: &'a Option<String>,
+                     on_input: fn(String) -> CredentialsMessage| {
+            text_input(placeholder, value.as_deref().unwrap_or_default())
+                .on_input(move |value| Message::CredentialsMessage(on_input(value)))
+                .width(200)
+        };
+        column![
+            row![
+                text("Basic auth"),
+                field(
+                    "username",
+                    &self.draft.username,
+                    CredentialsMessage::UsernameUpdated
+                ),
+                field(
+                    "password",
+                    &self.draft.password,
+                    CredentialsMessage::PasswordUpdated
+                )
+                .secure(true),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+            row![
+                text("Token"),
+                field(
+                    "parameter, e.g. auth",
+                    &self.draft.token_parameter,
+                    CredentialsMessage::TokenParameterUpdated
+                ),
+                field("token", &self.draft.token, CredentialsMessage::TokenUpdated).secure(true),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+            row![
+                button("Save Credentials").on_press(Message::CredentialsMessage(
+                    CredentialsMessage::Save(self.feed_id)
+                )),
+                button("Remove Credentials").on_press(Message::CredentialsMessage(
+                    CredentialsMessage::Clear(self.feed_id)
+                )),
+            ]
+            .spacing(10),
+        ]
+        .spacing(10)
+        .padding(10)
+        .into()
+    }
+}
@@ -1,7 +1,7 @@
 use super::feed::Feed;
-use crate::{
-    file_handling::feeds::get_feed_list_database, types::feeds::FeedMeta, ui::gui::Message,
-};
+use crate::{file_handling::library::Library, types::feeds::FeedMeta, ui::gui::Message};
+use std::sync::Arc;
+
 use iced::{
     alignment::Horizontal,
     widget::{container, text, Column, Scrollable},
@@ -16,7 +16,7 @@ impl FeedList {
     pub fn new(feeds: Vec<Feed>) -> Self {
         Self { feeds }
     }
-    pub fn view(&self) -> Element<Message> {
+    pub fn view(&self) -> Element<'_, Message> {
         match self.feeds.len() {
             0 => container(text("You don't follow any feeds yet."))
                 .padding(20)
@@ -36,14 +36,11 @@ impl FeedList {
             .into(),
         }
     }
-    pub async fn load_feeds() -> Result<Vec<FeedMeta>, String> {
-        let result = get_feed_list_database();
+    pub async fn load_feeds(library: Arc<dyn Library>) -> Result<Vec<FeedMeta>, String> {
+        let result = library.get_feeds();
         match result {
             Ok(res) => Ok(res),
-            Err(e) => Err(String::from(format!(
-                "Error fetching feeds from database: {:?}",
-                e
-            ))),
+            Err(e) => Err(format!("Error fetching feeds from database: {:?}", e)),
         }
     }
 }
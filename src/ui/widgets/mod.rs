@@ -1,6 +1,9 @@
 pub mod config;
+pub mod download_list;
 pub mod episode;
 pub mod episode_list;
 pub mod feed;
+pub mod feed_credentials;
 pub mod feed_list;
 pub mod player;
+pub mod show_header;
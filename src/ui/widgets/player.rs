@@ -1,38 +1,111 @@
 use iced::{
     alignment::Horizontal,
     time,
-    widget::{
-        button, container, progress_bar, progress_bar::Style, row, text, Renderer, Text, Theme,
-    },
-    Element, Length, Subscription,
+    widget::{button, container, pick_list, row, slider, text, Renderer, Text, Theme},
+    Alignment, Element, Length, Subscription,
 };
 use rodio::{Decoder, OutputStream, OutputStreamBuilder, Sink, Source};
-use std::{fs::File, io::BufReader, time::Duration};
+use std::{fmt, fs::File, io::BufReader, time::Duration};
 
-use crate::{file_handling::episodes::get_episode_by_id, ui::gui::Message};
+use crate::{
+    file_handling::{library::Library, paths::paths},
+    ui::gui::Message,
+};
+
+/// How often, in seconds of playback, the position is written back to the library.
+pub const POSITION_SAVE_INTERVAL_SECONDS: i32 = 15;
 
 #[allow(dead_code)] // Sink is the handle to the stream, but if stream is dropped, playback stops.
 #[derive(Default)]
 pub struct Player {
     pub id: Option<i32>,
+    pub feed_id: Option<i32>,
+    title: Option<String>,
     stream: Option<OutputStream>,
     pub sink: Option<Sink>,
     pub progress: f32,
     pub duration_seconds: f32,
+    pub saved_seconds: i32,
+    /// Set once the saved position has been reset while the episode plays, as marking it
+    /// played or unplayed does, so that playing on does not write the old position back.
+    /// Seeking clears it.
+    pub position_reset: bool,
+    pub played: bool,
+    /// Where the slider is being dragged to, if it is. Applied to the sink on release.
+    seeking_to: Option<f32>,
+    pub skip_back_seconds: u32,
+    pub skip_forward_seconds: u32,
+    pub speed: PlaybackSpeed,
+}
+
+/// A playback rate, where 1.0 is normal speed. rodio changes speed by resampling, so pitch
+/// rises and falls with it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlaybackSpeed(pub f32);
+
+impl Default for PlaybackSpeed {
+    fn default() -> Self {
+        Self(1.0)
+    }
 }
 
+impl fmt::Display for PlaybackSpeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x", self.0)
+    }
+}
+
+static PLAYBACK_SPEEDS: [PlaybackSpeed; 10] = [
+    PlaybackSpeed(0.5),
+    PlaybackSpeed(0.75),
+    PlaybackSpeed(1.0),
+    PlaybackSpeed(1.1),
+    PlaybackSpeed(1.25),
+    PlaybackSpeed(1.5),
+    PlaybackSpeed(1.75),
+    PlaybackSpeed(2.0),
+    PlaybackSpeed(2.5),
+    PlaybackSpeed(3.0),
+];
+
 #[derive(Clone, Debug)]
 pub enum PlayerMessage {
     Play,
     Pause,
     Progress,
+    Seek(f32),
+    SeekReleased,
+    SkipBack,
+    SkipForward,
+    SpeedChanged(PlaybackSpeed),
+}
+
+/// Formats a number of seconds as `m:ss`, or `h:mm:ss` once it reaches an hour.
+pub fn format_timestamp(seconds: f32) -> String {
+    let total = seconds.max(0.0) as u64;
+    let (hours, minutes, seconds) = (total / 3600, total / 60 % 60, total % 60);
+    match hours {
+        0 => format!("{minutes}:{seconds:02}"),
+        _ => format!("{hours}:{minutes:02}:{seconds:02}"),
+    }
+}
+
+/// How long an episode is, in seconds: what the decoder reports, or else what the feed said.
+/// MP3s without a Xing or VBRI header often leave the decoder guessing. Zero when neither
+/// knows.
+fn episode_duration(decoded: Option<Duration>, from_feed: Option<u32>) -> f32 {
+    decoded
+        .map(|duration| duration.as_secs_f32())
+        .filter(|seconds| *seconds > 0.0)
+        .or_else(|| from_feed.map(|seconds| seconds as f32))
+        .unwrap_or(0.0)
 }
 
 impl Player {
-    pub fn new(id: Option<i32>) -> Self {
+    pub fn new(library: &dyn Library, id: Option<i32>) -> Self {
         match id {
             None => Self::default(),
-            Some(id) => match get_episode_by_id(id) {
+            Some(id) => match library.get_episode_by_id(id) {
                 Err(e) => {
                     eprintln!("{:?}", e);
                     Self::default()
@@ -43,8 +116,8 @@ impl Player {
                         Self::default()
                     }
                     Ok(stream_handle) => {
-                        let sink = Sink::connect_new(&stream_handle.mixer());
-                        match File::open(format!("./episodes/{}", episode.file_name)) {
+                        let sink = Sink::connect_new(stream_handle.mixer());
+                        match File::open(paths().episode_file(&episode.file_name)) {
                             Err(e) => {
                                 eprintln!("{:?}", e);
                                 Self::default()
@@ -66,18 +139,39 @@ impl Player {
                                         Self::default()
                                     }
                                     Ok(source) => {
-                                        let duration_secs = match source.total_duration() {
-                                            Some(dur) => dur.as_secs_f32(),
-                                            None => 0.0,
-                                        };
+                                        let duration_secs = episode_duration(
+                                            source.total_duration(),
+                                            episode.duration_seconds,
+                                        );
                                         sink.append(source);
+                                        let speed = match library.get_feed_by_id(episode.feed_id) {
+                                            Ok(feed) => {
+                                                PlaybackSpeed(feed.playback_speed.unwrap_or(1.0))
+                                            }
+                                            Err(_) => PlaybackSpeed::default(),
+                                        };
+                                        sink.set_speed(speed.0);
+                                        let resume_at = episode.played_seconds.max(0);
+                                        if resume_at > 0 {
+                                            if let Err(e) =
+                                                sink.try_seek(Duration::from_secs(resume_at as u64))
+                                            {
+                                                eprintln!("{:?}", e);
+                                            }
+                                        }
                                         sink.play();
                                         Self {
                                             id: Some(episode.id),
+                                            feed_id: Some(episode.feed_id),
+                                            title: Some(episode.title),
                                             stream: Some(stream_handle),
                                             sink: Some(sink),
-                                            progress: 0.0,
+                                            progress: resume_at as f32,
                                             duration_seconds: duration_secs,
+                                            saved_seconds: resume_at,
+                                            played: episode.played,
+                                            speed,
+                                            ..Self::default()
                                         }
                                     }
                                 }
@@ -91,26 +185,91 @@ impl Player {
 
     pub fn update(&mut self, message: PlayerMessage) {
         match message {
-            PlayerMessage::Play => match &self.sink {
-                Some(sink) => sink.play(),
-                None => (),
-            },
-            PlayerMessage::Pause => match &self.sink {
-                Some(sink) => sink.pause(),
-                None => (),
-            },
-            PlayerMessage::Progress => match &self.sink {
-                Some(sink) => match sink.empty() {
-                    true => {
-                        self.progress = 0.0;
-                    }
-                    false => {
-                        let position = sink.get_pos();
-                        self.progress = position.as_secs_f32();
-                    }
-                },
-                None => (),
+            PlayerMessage::Play => {
+                if let Some(sink) = &self.sink {
+                    sink.play()
+                }
+            }
+            PlayerMessage::Pause => {
+                if let Some(sink) = &self.sink {
+                    sink.pause()
+                }
+            }
+            PlayerMessage::Progress => {
+                if let (Some(sink), None) = (&self.sink, self.seeking_to) {
+                    self.progress = match sink.empty() {
+                        true => 0.0,
+                        false => sink.get_pos().as_secs_f32(),
+                    };
+                }
+            }
+            PlayerMessage::Seek(position) => {
+                self.seeking_to = Some(position);
+                self.progress = position;
+            }
+            PlayerMessage::SeekReleased => {
+                if let Some(position) = self.seeking_to.take() {
+                    self.seek(position);
+                }
+            }
+            PlayerMessage::SkipBack => self.seek(self.progress - self.skip_back_seconds as f32),
+            PlayerMessage::SkipForward => {
+                self.seek(self.progress + self.skip_forward_seconds as f32)
+            }
+            PlayerMessage::SpeedChanged(speed) => {
+                self.speed = speed;
+                if let Some(sink) = &self.sink {
+                    sink.set_speed(speed.0)
+                }
+            }
+        }
+    }
+
+    /// Moves playback to `position` seconds, kept within the episode.
+    fn seek(&mut self, position: f32) {
+        if let Some(sink) = &self.sink {
+            let position = match self.duration_seconds > 0.0 {
+                true => position.clamp(0.0, self.duration_seconds),
+                false => position.max(0.0),
+            };
+            match sink.try_seek(Duration::from_secs_f32(position)) {
+                Ok(_) => {
+                    self.progress = position;
+                    self.position_reset = false;
+                }
+                Err(e) => eprintln!("{:?}", e),
+            }
+        }
+    }
+
+    /// Records that the library has forgotten this episode's position.
+    pub fn reset_position(&mut self) {
+        self.saved_seconds = 0;
+        self.position_reset = true;
+    }
+
+    /// The episode being played and how far into it playback is, in whole seconds. An episode
+    /// that has played to the end reports zero so it starts over next time.
+    pub fn position(&self) -> Option<(i32, i32)> {
+        match (self.id, &self.sink) {
+            (Some(id), Some(sink)) => match sink.empty() {
+                true => Some((id, 0)),
+                false => Some((id, sink.get_pos().as_secs() as i32)),
             },
+            _ => None,
+        }
+    }
+
+    /// Whether playback has drained or is within `threshold_seconds` of the end.
+    pub fn is_finishing(&self, threshold_seconds: u32) -> bool {
+        match &self.sink {
+            Some(sink) => {
+                sink.empty()
+                    || (self.duration_seconds > 0.0
+                        && self.duration_seconds - sink.get_pos().as_secs_f32()
+                            <= threshold_seconds as f32)
+            }
+            None => false,
         }
     }
 
@@ -119,32 +278,43 @@ impl Player {
             .map(|_| Message::PlayerMessage(PlayerMessage::Progress))])
     }
 
-    pub fn view(&self) -> Element<Message> {
-        let title: Text<Theme, Renderer> = match self.id {
-            Some(id) => {
-                let episode = get_episode_by_id(id);
-                match episode {
-                    Ok(episode) => text(format!("{}", episode.title)),
-                    Err(_) => text("Not Playing"),
-                }
-            }
+    pub fn view(&self) -> Element<'_, Message> {
+        let title: Text<Theme, Renderer> = match &self.title {
+            Some(title) => text(title),
             None => text("Not Playing"),
         };
+        // Without a known length there is nothing to seek along or count down from.
+        let timeline = (self.duration_seconds > 0.0).then(|| {
+            row![
+                slider(0.0..=self.duration_seconds, self.progress, |position| {
+                    Message::PlayerMessage(PlayerMessage::Seek(position))
+                })
+                .on_release(Message::PlayerMessage(PlayerMessage::SeekReleased)),
+                text(format!(
+                    "-{}",
+                    format_timestamp(self.duration_seconds - self.progress)
+                )),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center)
+        });
         container(
             row!(
                 title,
+                button(text(format!("-{}s", self.skip_back_seconds)))
+                    .on_press(Message::PlayerMessage(PlayerMessage::SkipBack)),
                 button(text("Play")).on_press(Message::PlayerMessage(PlayerMessage::Play)),
                 button(text("Pause")).on_press(Message::PlayerMessage(PlayerMessage::Pause)),
-                progress_bar(0.0..=self.duration_seconds, self.progress).style(|theme: &Theme| {
-                    let foo = theme.palette();
-                    Style {
-                        background: iced::Background::Color(foo.text),
-                        bar: iced::Background::Color(foo.success),
-                        border: iced::Border::default(),
-                    }
-                })
+                button(text(format!("+{}s", self.skip_forward_seconds)))
+                    .on_press(Message::PlayerMessage(PlayerMessage::SkipForward)),
+                text(format_timestamp(self.progress)),
             )
-            .spacing(10),
+            .push_maybe(timeline)
+            .push(pick_list(&PLAYBACK_SPEEDS[..], Some(self.speed), |speed| {
+                Message::PlayerMessage(PlayerMessage::SpeedChanged(speed))
+            }))
+            .spacing(10)
+            .align_y(Alignment::Center),
         )
         .width(Length::Fill)
         .padding(20)
@@ -152,3 +322,27 @@ impl Player {
         .into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0.0), "0:00");
+        assert_eq!(format_timestamp(75.9), "1:15");
+        assert_eq!(format_timestamp(3600.0 + 62.0), "1:01:02");
+        assert_eq!(format_timestamp(-5.0), "0:00");
+    }
+
+    #[test]
+    fn test_episode_duration_falls_back_to_the_feed() {
+        assert_eq!(
+            episode_duration(Some(Duration::from_secs(90)), Some(60)),
+            90.0
+        );
+        assert_eq!(episode_duration(None, Some(60)), 60.0);
+        assert_eq!(episode_duration(Some(Duration::ZERO), Some(60)), 60.0);
+        assert_eq!(episode_duration(None, None), 0.0);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{
+    types::feeds::FeedMeta,
+    ui::{gui::Message, widgets::episode::plain_text},
+};
+use iced::{
+    advanced::image::Handle,
+    widget::{column, container, image, row, text},
+    Element, Length,
+};
+
+/// How much of a show's description is shown above its episodes.
+const DESCRIPTION_CHARS: usize = 400;
+
+/// The top of a show's episode list: its artwork, title and what its feed says about it.
+pub struct ShowHeader<'a> {
+    feed: &'a FeedMeta,
+    image_handle: Option<Handle>,
+}
+
+impl<'a> ShowHeader<'a> {
+    pub fn new(feed: &'a FeedMeta, image_handle: Option<Handle>) -> Self {
+        Self { feed, image_handle }
+    }
+
+    pub fn view(&self) -> Element<'a, Message> {
+        let details = &self.feed.details;
+        let small = |value: String| text(value).size(12);
+        let about = column![text(self.feed.feed_title.to_owned().unwrap_or_default()).size(20)]
+            .push_maybe(
+                details
+                    .author
+                    .to_owned()
+                    .map(|author| text(author).size(14)),
+            )
+            .push_maybe(
+                Some(show_facts(self.feed))
+                    .filter(|facts| !facts.is_empty())
+                    .map(small),
+            )
+            .push_maybe(
+                details
+                    .description
+                    .as_deref()
+                    .map(|description| plain_text(description, DESCRIPTION_CHARS))
+                    .filter(|description| !description.is_empty())
+                    .map(text),
+            )
+            .push_maybe(details.website.to_owned().map(small))
+            .push_maybe(details.copyright.to_owned().map(small))
+            .spacing(4)
+            .width(Length::Fill);
+        let header = match &self.image_handle {
+            Some(handle) => row![image(handle).height(120), about].spacing(20),
+            None => row![about],
+        };
+        container(header).max_width(800).padding(20).into()
+    }
+}
+
+/// Categories, language, rating and type on one line, such as
+/// `Arts › Food · Leisure · en-us · Explicit · Serial`.
+fn show_facts(feed: &FeedMeta) -> String {
+    let details = &feed.details;
+    let rating = details.explicit.map(|explicit| match explicit {
+        true => String::from("Explicit"),
+        false => String::from("Clean"),
+    });
+    let kind = match details.show_type.as_deref() {
+        Some("serial") => Some(String::from("Serial")),
+        Some("episodic") => Some(String::from("Episodic")),
+        _ => None,
+    };
+    details
+        .categories
+        .iter()
+        .cloned()
+        .chain(details.language.to_owned())
+        .chain(rating)
+        .chain(kind)
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::feeds::FeedDetails;
+
+    #[test]
+    fn test_show_facts() {
+        let mut feed = FeedMeta {
+            id: 1,
+            feed_url: String::from("https://example.com/feed.xml"),
+            xml_file_path: None,
+            feed_title: None,
+            image_file_path: None,
+            playback_speed: None,
+            etag: None,
+            last_modified: None,
+            credentials: None,
+            details: FeedDetails::default(),
+        };
+        assert_eq!(show_facts(&feed), "");
+        feed.details = FeedDetails {
+            categories: vec![String::from("Arts › Food"), String::from("Leisure")],
+            language: Some(String::from("en-us")),
+            explicit: Some(true),
+            show_type: Some(String::from("serial")),
+            ..FeedDetails::default()
+        };
+        assert_eq!(
+            show_facts(&feed),
+            "Arts › Food · Leisure · en-us · Explicit · Serial"
+        );
+    }
+}